@@ -1,4 +1,4 @@
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -17,6 +17,14 @@ pub struct Cli {
     #[arg(long, value_name = "FILE", default_value_os_t = crate::config::default_config_path())]
     pub config: PathBuf,
 
+    /// Act on this project instead of the current one
+    #[arg(short, long, global = true, value_name = "NAME")]
+    pub project: Option<String>,
+
+    /// Use a named profile from the config (default: $PROJCTL_PROFILE)
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -27,20 +35,145 @@ pub enum Commands {
     Add(AddArgs),
     /// Switch to a new project
     Use {
-        /// Project name
+        /// Project name, or `-` for the previously used project
         name: Option<String>,
+        /// Only offer projects carrying this tag (repeatable)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Include archived projects
+        #[arg(long)]
+        archived: bool,
+        /// Print shell code that switches only the calling shell (used by
+        /// the `shell-init` wrapper)
+        #[arg(long, value_enum, hide = true)]
+        emit: Option<Shell>,
+    },
+    /// Switch to a project, saving the current one for `pop`
+    Push {
+        /// Project name
+        name: String,
+        #[arg(long, value_enum, hide = true)]
+        emit: Option<Shell>,
+    },
+    /// Switch back to the project saved by the last `push`
+    Pop {
+        #[arg(long, value_enum, hide = true)]
+        emit: Option<Shell>,
     },
+    /// Show the project stack
+    Stack,
     /// List all added projects (including auto-detected)
-    List,
+    List {
+        /// Only show projects carrying this tag (repeatable)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Include archived projects
+        #[arg(long)]
+        archived: bool,
+    },
     /// Remove a named project
     Remove {
         /// Project name
         name: String,
     },
+    /// Rename a project (keeps current project and tmux session)
+    Rename {
+        /// Current project name
+        old: String,
+        /// New project name
+        new: String,
+    },
+    /// Point a project at a new folder, moving it if needed
+    Move {
+        /// Project name
+        name: String,
+        /// New project path
+        path: String,
+    },
+    /// Remove projects whose folder no longer exists
+    Prune {
+        /// Only show what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Find moved projects by git remote and update their paths
+    Relink {
+        /// Only relink this project (default: every missing project)
+        name: Option<String>,
+    },
+    /// Write the registry to a portable file
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+        /// Output file (default: stdout)
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Add projects from a file written by `projctl export`
+    Import {
+        /// File to import (.json or .toml)
+        file: PathBuf,
+        /// What to do when a project name is already taken
+        #[arg(long, value_enum, default_value_t = ConflictStrategy::Skip)]
+        on_conflict: ConflictStrategy,
+    },
     /// Print current project path (or named project's path)
     Path {
         /// Optional project name
         name: Option<String>,
+        /// Pick among projects carrying this tag (repeatable)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Include archived projects
+        #[arg(long)]
+        archived: bool,
+    },
+    /// Go to a project folder, or a subdirectory of it (needs `shell-init`
+    /// to change the shell's directory; otherwise prints the path)
+    Cd {
+        /// Project name (defaults to the current project)
+        name: Option<String>,
+        /// Subdirectory inside the project
+        subdir: Option<PathBuf>,
+        /// Print shell code that changes directory (used by the
+        /// `shell-init` wrapper)
+        #[arg(long, value_enum, hide = true)]
+        emit: Option<Shell>,
+    },
+    /// Print the registered project that owns a path
+    Which {
+        /// Path to look up (defaults to the current directory)
+        path: Option<PathBuf>,
+    },
+    /// Add or remove project tags
+    Tag {
+        #[command(subcommand)]
+        action: TagCommands,
+    },
+    /// Hide a project from `list` and the `use` picker, keeping its entry
+    Archive {
+        /// Project name
+        name: String,
+    },
+    /// Restore an archived project
+    Unarchive {
+        /// Project name
+        name: String,
+    },
+    /// Pin a project to the top of the `use` picker
+    Pin {
+        /// Project name
+        name: String,
+    },
+    /// Unpin a project
+    Unpin {
+        /// Project name
+        name: String,
+    },
+    /// Add or remove project aliases
+    Alias {
+        #[command(subcommand)]
+        action: AliasCommands,
     },
     /// Run a command inside the current project
     Run {
@@ -48,7 +181,12 @@ pub enum Commands {
         cmd: Vec<String>,
     },
     /// Open editor in current project
-    Edit,
+    Edit {
+        /// File to open, relative to the project root (`file:line` jumps to a line)
+        target: Option<String>,
+    },
+    /// Open the current project in the file manager
+    Open,
     /// Open git UI in current project
     Git,
     /// Open logs in current project (or given path)
@@ -56,6 +194,34 @@ pub enum Commands {
         /// Optional path
         path: Option<String>,
     },
+    /// Open the current project's notes, or append a line to them
+    Note {
+        /// Text to append instead of opening the editor
+        text: Vec<String>,
+    },
+    /// Manage named links (dashboards, repos, ...) for the current project
+    Link {
+        #[command(subcommand)]
+        action: LinkCommands,
+    },
+    /// Open one of the current project's links in the browser
+    Browse {
+        /// Link name (picker if omitted)
+        link: Option<String>,
+    },
+    /// Manage commands run when switching to/away from the current project
+    Hook {
+        #[command(subcommand)]
+        action: HookCommands,
+    },
+    /// Print the current project's environment as `export` lines, or edit it
+    Env {
+        #[command(subcommand)]
+        action: Option<EnvCommands>,
+        /// Shell syntax for the printed lines
+        #[arg(long, value_enum, default_value = "bash")]
+        shell: Shell,
+    },
     /// Setup/attach tmux servers session for current project
     Servers {
         /// Setup flag (creates/reseeds if needed)
@@ -68,10 +234,219 @@ pub enum Commands {
         #[arg(long, conflicts_with_all = ["refresh", "reset"])]
         kill: bool,
     },
+    /// Print shell integration (per-shell current project) to eval in your rc file
+    ShellInit {
+        #[arg(value_enum)]
+        shell: Shell,
+        /// Don't switch projects automatically when cd-ing into one
+        #[arg(long)]
+        no_auto_switch: bool,
+    },
+    /// Print a short prompt segment: project, git branch, servers running
+    Prompt {
+        /// Template with {name}, {branch} and {servers} placeholders
+        #[arg(long)]
+        format: Option<String>,
+    },
+    /// Switch this shell to the project containing the cwd (used by the
+    /// `shell-init` cd hook)
+    #[command(hide = true)]
+    Chpwd {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Completion candidates for the shell integration
+    #[command(hide = true)]
+    Complete {
+        #[command(subcommand)]
+        what: CompleteCommands,
+    },
+    /// Inspect and edit projctl's config
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommands,
+    },
+    /// Manage config profiles
+    Profile {
+        #[command(subcommand)]
+        action: ProfileCommands,
+    },
     /// Create a Postgres DB
     DbCreate {
-        /// Database name
-        name: String,
+        /// Database name (defaults to `database` from .projctl.toml)
+        name: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    Json,
+    Toml,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ConflictStrategy {
+    /// Keep the existing project
+    Skip,
+    /// Replace the existing project
+    Overwrite,
+    /// Import under a new name (`name-2`, `name-3`, ...)
+    Rename,
+}
+
+#[derive(Subcommand)]
+pub enum CompleteCommands {
+    /// Project names and aliases
+    Projects,
+    /// Subdirectories of a project matching a partial path
+    Subdirs {
+        /// Project name
+        project: String,
+        /// Partial path typed so far
+        #[arg(default_value = "")]
+        prefix: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TagCommands {
+    /// Tag a project
+    Add {
+        /// Project name
+        project: String,
+        /// Tag to add
+        tag: String,
+    },
+    /// Remove a tag from a project
+    Rm {
+        /// Project name
+        project: String,
+        /// Tag to remove
+        tag: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum LinkCommands {
+    /// Add or replace a named link
+    Add {
+        /// Link name
+        label: String,
+        /// URL
+        url: String,
+    },
+    /// Remove a named link
+    Rm {
+        /// Link name
+        label: String,
+    },
+    /// Show the current project's links
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum ProfileCommands {
+    /// List profiles with their registries
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Print the effective config and where each value comes from
+    Show,
+    /// Print a single effective value
+    Get { key: String },
+    /// Set a value in the user config (or the project's .projctl.toml)
+    Set {
+        key: String,
+        value: String,
+        /// Write to the current project's .projctl.toml
+        #[arg(long)]
+        local: bool,
+    },
+    /// Open the config file in your editor
+    Edit {
+        /// Edit the current project's .projctl.toml
+        #[arg(long)]
+        local: bool,
+    },
+    /// Check config files for unknown keys and wrong types
+    Validate,
+}
+
+#[derive(Subcommand)]
+pub enum HookCommands {
+    /// Set the command run on entering or leaving the project
+    Set {
+        #[arg(value_enum)]
+        event: HookEvent,
+        /// Shell command (run with `sh -c` in the project dir)
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        cmd: Vec<String>,
+    },
+    /// Remove hooks (both unless an event is given)
+    Clear {
+        #[arg(value_enum)]
+        event: Option<HookEvent>,
+    },
+    /// Show the current project's hooks
+    List,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HookEvent {
+    Enter,
+    Leave,
+}
+
+#[derive(Subcommand)]
+pub enum EnvCommands {
+    /// Set a variable for the current project
+    Set {
+        /// Variable name
+        key: String,
+        /// Value
+        value: String,
+    },
+    /// Remove a variable
+    Unset {
+        /// Variable name
+        key: String,
+    },
+    /// Load a `.env` file (relative to the project dir)
+    AddFile {
+        /// Path to the file
+        file: String,
+    },
+    /// Stop loading a `.env` file
+    RmFile {
+        /// Path to the file
+        file: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AliasCommands {
+    /// Add an alternative name for a project
+    Add {
+        /// Project name
+        project: String,
+        /// Alias to add
+        alias: String,
+    },
+    /// Remove an alias from a project
+    Rm {
+        /// Project name
+        project: String,
+        /// Alias to remove
+        alias: String,
     },
 }
 
@@ -1,38 +1,59 @@
-use crate::config::ResolvedConfig;
-use crate::models::{Projects, load_projects, save_projects};
-use crate::servers::setup_servers;
+use crate::cli::{
+    AliasCommands, CompleteCommands, ConfigCommands, ConflictStrategy, EnvCommands, ExportFormat,
+    HookCommands, HookEvent, LinkCommands, ProfileCommands, Shell, TagCommands,
+};
+use crate::config::{
+    ConfigKind, ConfigSources, FileConfig, PROJECT_FILE, ResolvedConfig, SETTABLE_PROJECT_KEYS,
+    SETTABLE_USER_KEYS, Tool, set_key, validate_text,
+};
+use crate::environment::{is_var_name, project_env};
+use crate::exchange::{EXPORT_VERSION, Export};
+use crate::models::{ProjectEntry, Projects};
+use crate::resolve::{MatchKind, resolve_exact, resolve_project, resolve_project_in};
+use crate::servers::{
+    kill_session, rename_session, session_marked_running, session_name, session_running,
+    setup_servers,
+};
+use crate::shell::{PREVIOUS_VAR, PROJECT_VAR, STACK_VAR, cd_line, export_var};
+use crate::store::{JsonFileStore, ProjectStore};
 use crate::utils::{
-    autodetected_projects, canon, get_autodetected_projdir, get_current_projdir, get_projdir,
-    get_projects_dir, get_state, parse_cmd, same_path,
+    PROJECTS_DB, autodetected_projects, canon, config_dir, expand_cmd, get_autodetected_projdir,
+    get_current_projdir, get_state, git_branch, git_remote_url, has_note, normalize_remote,
+    note_path, profile, profile_dir, project_containing, same_path,
 };
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow};
 use console::{Emoji, style};
 use dialoguer::{Confirm, MultiSelect, Select, theme::ColorfulTheme};
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use std::{cmp, env, fs};
+use std::process::{Command, Stdio};
+use std::{cmp, env, fs, io};
 use which;
 
-pub fn add_args(args: crate::cli::AddArgs) -> Result<()> {
+pub fn add_args(
+    store: &dyn ProjectStore,
+    cfg: &ResolvedConfig,
+    args: crate::cli::AddArgs,
+) -> Result<()> {
     match (args.name.as_deref(), args.path.as_deref()) {
-        (None, None) => add_interactive_from_auto(),
-        (Some(name), None) => add_auto_by_name(name),
-        (Some(name), Some(path)) => add_named_path(name, path),
+        (None, None) => add_interactive_from_auto(store, cfg),
+        (Some(name), None) => add_auto_by_name(store, cfg, name),
+        (Some(name), Some(path)) => add_named_path(store, name, path),
         (None, Some(_)) => anyhow::bail!("Path given but no name. Use projctl add <name> <path>"),
     }
 }
 
-fn add_interactive_from_auto() -> Result<()> {
-    let mut projects = load_projects()?;
+fn add_interactive_from_auto(store: &dyn ProjectStore, cfg: &ResolvedConfig) -> Result<()> {
+    let projects = store.load()?;
 
     use std::collections::HashSet;
     let tracked: HashSet<_> = projects
         .projects
         .values()
-        .map(|p| canon(Path::new(p)))
+        .map(|e| canon(Path::new(&e.path)))
         .collect();
 
-    let autos: Vec<(String, PathBuf)> = autodetected_projects()
+    let autos: Vec<(String, PathBuf)> = autodetected_projects(cfg)
         .into_iter()
         .filter(|(_n, p)| !tracked.contains(&canon(p)))
         .collect();
@@ -40,7 +61,7 @@ fn add_interactive_from_auto() -> Result<()> {
     if autos.is_empty() {
         println!(
             "No auto-detected projects to add in `{}`.",
-            get_projects_dir().display()
+            cfg.projects_dir.display()
         );
         return Ok(());
     }
@@ -60,104 +81,168 @@ fn add_interactive_from_auto() -> Result<()> {
         return Ok(());
     }
 
-    for i in idxs {
-        let (name, path) = &autos[i];
-        insert_project(&mut projects, name.clone(), path.clone())?;
-    }
-    save_projects(&projects)?;
-    Ok(())
+    store.update(|projects| {
+        for i in idxs {
+            let (name, path) = &autos[i];
+            insert_project(projects, name.clone(), path.clone())?;
+        }
+        Ok(())
+    })
 }
 
-fn add_auto_by_name(auto_name: &str) -> Result<()> {
-    let found = autodetected_projects()
+fn add_auto_by_name(store: &dyn ProjectStore, cfg: &ResolvedConfig, auto_name: &str) -> Result<()> {
+    let found = autodetected_projects(cfg)
         .into_iter()
         .find(|(n, _)| n == auto_name);
     let Some((name, path)) = found else {
         anyhow::bail!(
             "Auto-detected project '{}' not found under {}",
             auto_name,
-            get_projects_dir().display()
+            cfg.projects_dir.display()
         );
     };
-    let mut projects = load_projects()?;
-    insert_project(&mut projects, name, path)?;
-    save_projects(&projects)?;
-    Ok(())
+    store.update(|projects| insert_project(projects, name, path))
 }
 
-fn add_named_path(name: &str, path: &str) -> Result<()> {
+fn add_named_path(store: &dyn ProjectStore, name: &str, path: &str) -> Result<()> {
+    check_name(name)?;
     let abs = canon(Path::new(path));
-    let mut projects = load_projects()?;
+    let entry = new_entry(&abs);
+    store.update(|projects| {
+        if projects.projects.contains_key(name) {
+            anyhow::bail!("Project '{}' already exists.", name);
+        }
 
-    if projects.projects.contains_key(name) {
-        anyhow::bail!("Project '{}' already exists.", name);
-    }
+        if let Some((existing, _)) = projects
+            .projects
+            .iter()
+            .find(|(_n, e)| same_path(Path::new(&e.path), &abs))
+        {
+            anyhow::bail!("That path is already tracked as '{}'.", existing);
+        }
+        projects.projects.insert(name.to_string(), entry);
+        Ok(())
+    })?;
+    println!("Added projects {} -> {}", name, abs.display());
+    Ok(())
+}
 
-    if let Some((existing, _)) = projects
-        .projects
-        .iter()
-        .find(|(_n, p)| same_path(Path::new(p), &abs))
-    {
-        anyhow::bail!("That path is already tracked as '{}'.", existing);
+fn new_entry(path: &Path) -> ProjectEntry {
+    let mut entry = ProjectEntry::new(path.display().to_string());
+    entry.remote = git_remote_url(path);
+    entry
+}
+
+/// Names given by hand or in an import. `-` is `use -`, and a `/` would read
+/// as a path below a scan root.
+fn check_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() || name == "-" || name.contains(['/', '\\']) {
+        anyhow::bail!("Invalid project name '{}'", name);
     }
-    projects
-        .projects
-        .insert(name.to_string(), abs.display().to_string());
-    save_projects(&projects)?;
-    println!("Added projects {} -> {}", name, abs.display());
     Ok(())
 }
 
+/// Status goes to stderr: `use --emit` reaches this, and the shell wrapper
+/// evaluates its stdout.
 fn insert_project(projects: &mut Projects, name: String, path: PathBuf) -> Result<()> {
-    if projects.projects.contains_key(&name) {
+    if let Some(existing) = projects.projects.get_mut(&name) {
         if !Confirm::new()
             .with_prompt(format!("Project '{}' exists. Overwrite path?", name))
             .default(false)
             .interact()?
         {
-            println!("Skipped '{}'", name);
+            eprintln!("Skipped '{}'", name);
             return Ok(());
         }
+        existing.path = path.display().to_string();
+        existing.remote = git_remote_url(&path);
+        eprintln!("Updated '{}'", name);
+        return Ok(());
     } else if projects
         .projects
         .values()
-        .any(|p| same_path(Path::new(p), &path))
+        .any(|e| same_path(Path::new(&e.path), &path))
     {
         let existing = projects
             .projects
             .iter()
-            .find(|(_n, p)| same_path(Path::new(p), &path))
+            .find(|(_n, e)| same_path(Path::new(&e.path), &path))
             .unwrap()
             .0;
-        println!(
+        eprintln!(
             "Skipping '{}': path already tracked as '{}'.",
             name, existing
         );
         return Ok(());
     }
 
-    projects
-        .projects
-        .insert(name.clone(), path.display().to_string());
-    println!("Added '{}'", name);
+    projects.projects.insert(name.clone(), new_entry(&path));
+    eprintln!("Added '{}'", name);
     Ok(())
 }
 
-pub fn use_proj(name: Option<String>) -> Result<()> {
-    match name {
-        Some(n) => use_by_name(&n),
-        None => use_interactive(),
+pub fn use_proj(
+    store: &dyn ProjectStore,
+    cfg: &ResolvedConfig,
+    name: Option<String>,
+    tags: &[String],
+    archived: bool,
+    emit: Option<Shell>,
+) -> Result<()> {
+    match name.as_deref() {
+        Some("-") => use_previous(store, emit),
+        Some(n) => use_by_name(store, cfg, n, tags, archived, emit),
+        None => use_interactive(store, cfg, tags, archived, emit),
     }
 }
 
-fn use_by_name(name: &str) -> Result<()> {
-    // Enabled
-    if let Some(p) = get_projdir(name)? {
-        return switch_to(name, Path::new(&p), /*persist_db_current=*/ true);
+/// `projctl use -`: toggle back to the previously used project, like `cd -`.
+/// Inside the shell wrapper this is the shell's own previous project.
+fn use_previous(store: &dyn ProjectStore, emit: Option<Shell>) -> Result<()> {
+    let projects = store.load()?;
+    let shell_var = |var| {
+        emit.and_then(|_| env::var(var).ok())
+            .filter(|n| !n.is_empty())
+    };
+    let shell_prev = shell_var(PREVIOUS_VAR).filter(|p| projects.projects.contains_key(p));
+    let prev = match (&shell_prev, shell_var(PROJECT_VAR)) {
+        (Some(prev), _) => Some(prev.as_str()),
+        (None, Some(current)) => projects.previous_of(Some(&current)),
+        (None, None) => projects.previous(),
+    };
+    let Some(prev) = prev else {
+        anyhow::bail!("No previous project to switch back to");
+    };
+    let entry = &projects.projects[prev];
+    if entry.archived {
+        warn_archived(prev);
     }
+    switch_to(store, prev, Path::new(&entry.path), true, emit)
+}
 
-    // Auto-detected
-    if let Some(p) = get_autodetected_projdir(name) {
+fn warn_archived(name: &str) {
+    eprintln!(
+        "{} '{}' is archived. Run `projctl unarchive {}` to restore it.",
+        style("warning:").yellow(),
+        name,
+        name
+    );
+}
+
+fn use_by_name(
+    store: &dyn ProjectStore,
+    cfg: &ResolvedConfig,
+    name: &str,
+    tags: &[String],
+    archived: bool,
+    emit: Option<Shell>,
+) -> Result<()> {
+    let projects = store.load()?;
+
+    // Auto-detected, unless the name is already an added project or alias
+    if resolve_exact(&projects, name).is_none()
+        && let Some(p) = get_autodetected_projdir(cfg, name)
+    {
         let add = Confirm::new()
             .with_prompt(format!(
                 "'{}' is auto-detected but not added. Add now?",
@@ -166,40 +251,216 @@ fn use_by_name(name: &str) -> Result<()> {
             .default(true)
             .interact()?;
         if add {
-            let mut projects = load_projects()?;
-            insert_project(&mut projects, name.to_string(), p.clone())?;
-            save_projects(&projects)?;
-            return switch_to(name, &p, true);
+            store.update(|projects| insert_project(projects, name.to_string(), p.clone()))?;
+            return switch_to(store, name, &p, true, emit);
         } else {
             // Just switch don't save
-            return switch_to(name, &p, false);
+            return switch_to(store, name, &p, false, emit);
         }
     }
-    anyhow::bail!(
-        "Project '{}' not found. Hint: run `projctl add` to add it.",
-        name
+
+    // Enabled
+    let resolved = resolve_project_in(&projects, name, archived)?;
+    let entry = &projects.projects[&resolved.name];
+    if !entry.has_tags(tags) {
+        anyhow::bail!(
+            "Project '{}' is not tagged {}",
+            resolved.name,
+            tags.join(", ")
+        );
+    }
+    if entry.archived {
+        warn_archived(&resolved.name);
+    }
+    switch_to(
+        store,
+        &resolved.name,
+        Path::new(&entry.path),
+        /*persist_db_current=*/ true,
+        emit,
     )
 }
 
-fn use_interactive() -> Result<()> {
-    let projects = load_projects()?;
-    let items: Vec<(String, String)> = projects
-        .projects
+fn use_interactive(
+    store: &dyn ProjectStore,
+    cfg: &ResolvedConfig,
+    tags: &[String],
+    archived: bool,
+    emit: Option<Shell>,
+) -> Result<()> {
+    let projects = store.load()?;
+    let Some((name, path_str)) =
+        pick_project(store, cfg, &projects, tags, archived, "Select project")?
+    else {
+        return Ok(());
+    };
+    switch_to(store, &name, Path::new(&path_str), true, emit)
+}
+
+/// `projctl push`: switch to `name`, remembering the current project on the
+/// stack for `projctl pop`. With the shell wrapper the stack is the shell's
+/// own, like its current project.
+pub fn push(store: &dyn ProjectStore, name: String, emit: Option<Shell>) -> Result<()> {
+    let projects = store.load()?;
+    let Some(from) = leaving_project(store, emit)? else {
+        anyhow::bail!("No current project to come back to. Use `projctl use` instead.");
+    };
+    // PROJCTL_PROJECT or a stale `current` may name anything
+    if !projects.projects.contains_key(&from) {
+        anyhow::bail!(
+            "Current project '{}' is not an added project, so it can't go on the stack. Use `projctl use` instead.",
+            from
+        );
+    }
+    let resolved = resolve_project(&projects, &name)?;
+    if resolved.name == from {
+        anyhow::bail!("Already on '{}'", from);
+    }
+    let path = PathBuf::from(&projects.projects[&resolved.name].path);
+    switch_to(store, &resolved.name, &path, true, emit)?;
+    // Only once the switch went through
+    match emit {
+        Some(shell) => {
+            let mut stack = shell_stack();
+            stack.push(from);
+            println!("{}", export_var(shell, STACK_VAR, &stack.join("\n"))?);
+        }
+        None => store.update(|projects| {
+            projects.stack.push(from);
+            Ok(())
+        })?,
+    }
+    Ok(())
+}
+
+/// `projctl pop`: switch back to the project saved by the last `push`.
+pub fn pop(store: &dyn ProjectStore, emit: Option<Shell>) -> Result<()> {
+    let projects = store.load()?;
+    let mut stack = match emit {
+        Some(_) => shell_stack(),
+        None => projects.stack.clone(),
+    };
+    let Some(name) = stack.pop() else {
+        anyhow::bail!("Project stack is empty");
+    };
+    // A missing entry is dropped either way, so the next pop can go on
+    let path = projects.projects.get(&name).map(|e| PathBuf::from(&e.path));
+    if let Some(path) = &path {
+        switch_to(store, &name, path, true, emit)?;
+    }
+    match emit {
+        Some(shell) => println!("{}", export_var(shell, STACK_VAR, &stack.join("\n"))?),
+        None => store.update(|projects| {
+            if projects.stack.last() == Some(&name) {
+                projects.stack.pop();
+            }
+            Ok(())
+        })?,
+    }
+    if path.is_none() {
+        let message = format!(
+            "'{}' is no longer an added project; dropped it from the stack",
+            name
+        );
+        // The wrapper only evaluates the output of a successful run
+        match emit {
+            Some(_) => eprintln!("{} {}", style("error:").red(), message),
+            None => anyhow::bail!(message),
+        }
+    }
+    Ok(())
+}
+
+/// `PROJCTL_STACK` as set by earlier pushes in this shell.
+fn shell_stack() -> Vec<String> {
+    env::var(STACK_VAR)
+        .unwrap_or_default()
+        .lines()
+        .filter(|n| !n.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// `projctl stack`: the current project, then the pushed ones (next pop first).
+pub fn stack(store: &dyn ProjectStore, cfg: &ResolvedConfig) -> Result<()> {
+    let projects = store.load()?;
+    let current = get_current_projdir(store, cfg, None)
+        .ok()
+        .map(|dir| project_name_for(store, &dir))
+        .transpose()?;
+    if let Some(cur) = &current {
+        println!("{} {}", style("*").green(), style(cur).bold());
+    }
+    // A shell using the wrapper has its own stack
+    let in_shell = [PROJECT_VAR, STACK_VAR]
         .iter()
-        .map(|(n, p)| (n.clone(), p.clone()))
+        .any(|v| env::var_os(v).is_some_and(|v| !v.is_empty()));
+    let pushed = match in_shell {
+        true => shell_stack(),
+        false => projects.stack,
+    };
+    for name in pushed.iter().rev() {
+        println!("  {}", name);
+    }
+    if pushed.is_empty() {
+        println!("{}", style("stack is empty").dim());
+    }
+    Ok(())
+}
+
+/// Show a picker over added projects carrying all of `tags`. Returns `None`
+/// (after printing a hint) when there is nothing to pick from.
+fn pick_project(
+    store: &dyn ProjectStore,
+    cfg: &ResolvedConfig,
+    projects: &Projects,
+    tags: &[String],
+    archived: bool,
+    prompt: &str,
+) -> Result<Option<(String, String)>> {
+    let items: Vec<(String, String)> = projects
+        .ordered()
+        .into_iter()
+        .filter(|(_, e)| e.has_tags(tags) && (archived || !e.archived))
+        .map(|(n, e)| (n.clone(), e.path.clone()))
         .collect();
 
     if items.is_empty() {
-        println!(
-            "No added projects. Hint: run `projctl add` to add from '{}'.",
-            get_projects_dir().display()
-        );
-        return Ok(());
+        if tags.is_empty() {
+            eprintln!(
+                "No added projects. Hint: run `projctl add` to add from '{}'.",
+                cfg.projects_dir.display()
+            );
+        } else {
+            eprintln!("No projects tagged {}.", tags.join(", "));
+        }
+        return Ok(None);
+    }
+    if items.len() == 1 {
+        return Ok(items.into_iter().next());
     }
 
-    let current_path = get_current_projdir().ok().map(|p| canon(&p));
+    let current_path = get_current_projdir(store, cfg, None)
+        .ok()
+        .map(|p| canon(&p));
 
-    let labels: Vec<String> = items.iter().map(|(n, p)| format!("{n}    {p}")).collect();
+    let pin = Emoji("★ ", "* ");
+    let labels: Vec<String> = items
+        .iter()
+        .map(|(n, p)| {
+            let marker = if projects.projects[n].pinned {
+                pin.to_string()
+            } else {
+                "  ".to_string()
+            };
+            let note = if has_note(n) {
+                format!(" {}", Emoji("✎", "(n)"))
+            } else {
+                String::new()
+            };
+            format!("{marker}{n}{note}    {p}")
+        })
+        .collect();
 
     let default_idx = items
         .iter()
@@ -207,46 +468,242 @@ fn use_interactive() -> Result<()> {
         .unwrap_or(0);
 
     let idx = Select::with_theme(&ColorfulTheme::default())
-        .with_prompt("Select project")
+        .with_prompt(prompt)
         .items(&labels)
         .default(default_idx)
         .interact()?;
 
-    let (name, path_str) = &items[idx];
-    switch_to(name, Path::new(path_str), true)
+    Ok(Some(items[idx].clone()))
 }
 
-fn switch_to(name: &str, path: &Path, persist_db_current: bool) -> Result<()> {
+fn switch_to(
+    store: &dyn ProjectStore,
+    name: &str,
+    path: &Path,
+    persist_db_current: bool,
+    emit: Option<Shell>,
+) -> Result<()> {
+    let from = leaving_project(store, emit)?;
+    let changed = from.as_deref() != Some(name);
+    if changed && let Some(from) = &from {
+        run_hook(store, from, HookEvent::Leave);
+    }
     if persist_db_current {
-        let mut projects = load_projects()?;
-        projects.current = Some(name.to_string());
-        save_projects(&projects)?;
+        let remote = git_remote_url(path);
+        store.update(|projects| {
+            // A per-shell switch leaves the global current project alone
+            if emit.is_some() {
+                projects.touch(name);
+            } else {
+                projects.record_use(name);
+            }
+            if let Some(entry) = projects.projects.get_mut(name)
+                && remote.is_some()
+            {
+                entry.remote = remote;
+            }
+            Ok(())
+        })?;
+    }
+    if let Some(shell) = emit {
+        // stdout is eval'd by the shell wrapper; talk to the user on stderr
+        emit_current(shell, name)?;
+        println!("{}", cd_line(shell, path));
+        eprintln!("Switched to project '{}' ({})", name, path.display());
+    } else {
+        write_state(path)?;
+        println!("Switched to project '{}' ({})", name, path.display());
+    }
+    if changed {
+        run_hook(store, name, HookEvent::Enter);
     }
+    Ok(())
+}
+
+/// The project a switch moves away from: the shell's own with the wrapper
+/// (once it has one), the global current project otherwise.
+fn leaving_project(store: &dyn ProjectStore, emit: Option<Shell>) -> Result<Option<String>> {
+    if emit.is_some()
+        && let Ok(name) = env::var(PROJECT_VAR)
+        && !name.is_empty()
+    {
+        return Ok(Some(name));
+    }
+    Ok(store.load()?.current)
+}
+
+/// Run a project's `on_enter`/`on_leave` hook with `sh -c` in the project
+/// dir. Failures are reported but never block the switch. Output goes to
+/// stderr, since the shell wrapper evaluates stdout.
+fn run_hook(store: &dyn ProjectStore, name: &str, event: HookEvent) {
+    let Ok(projects) = store.load() else {
+        return;
+    };
+    let Some(entry) = projects.projects.get(name) else {
+        return;
+    };
+    let hook = match event {
+        HookEvent::Enter => &entry.on_enter,
+        HookEvent::Leave => &entry.on_leave,
+    };
+    let Some(cmd) = hook else {
+        return;
+    };
+    let dir = Path::new(&entry.path);
+    let mut vars = project_env(store, dir).unwrap_or_default();
+    vars.insert("PROJCTL_PROJECT_NAME".into(), name.to_string());
+    vars.insert("PROJCTL_PROJECT_DIR".into(), entry.path.clone());
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .current_dir(dir)
+        .envs(&vars)
+        .stdout(Stdio::from(io::stderr()))
+        .status();
+    let failure = match status {
+        Ok(s) if s.success() => return,
+        Ok(s) => s.to_string(),
+        Err(e) => e.to_string(),
+    };
+    eprintln!(
+        "{} {} hook for '{}' failed: {}",
+        style("warning:").yellow(),
+        event_label(event),
+        name,
+        failure
+    );
+}
+
+/// Shell lines making `name` this shell's current project.
+fn emit_current(shell: Shell, name: &str) -> Result<()> {
+    if let Ok(prev) = env::var(PROJECT_VAR)
+        && !prev.is_empty()
+        && prev != name
+    {
+        println!("{}", export_var(shell, PREVIOUS_VAR, &prev)?);
+    }
+    println!("{}", export_var(shell, PROJECT_VAR, name)?);
+    Ok(())
+}
+
+/// `projctl chpwd`: called by the shell hook on every directory change.
+/// Switches this shell to the registered project containing the cwd, if it
+/// isn't current already; leaving a project keeps it current. The registry
+/// is only read: a write here would rotate its backups on every `cd`.
+pub fn chpwd(store: &dyn ProjectStore, shell: Shell) -> Result<()> {
+    let cwd = env::current_dir()?;
+    let Ok(projects) = store.load() else {
+        return Ok(());
+    };
+    let Some((name, _)) = project_containing(&projects, &cwd) else {
+        return Ok(());
+    };
+    let from = leaving_project(store, Some(shell))?;
+    if from.as_deref() == Some(name.as_str()) {
+        return Ok(());
+    }
+    if let Some(from) = &from {
+        run_hook(store, from, HookEvent::Leave);
+    }
+    emit_current(shell, &name)?;
+    eprintln!("{}", style(format!("projctl: now on '{name}'")).dim());
+    run_hook(store, &name, HookEvent::Enter);
+    Ok(())
+}
+
+/// `projctl prompt`: project name, git branch and whether its servers session
+/// is up. Reads files only (no git/tmux processes) so it stays prompt-fast.
+/// Prints nothing outside a project.
+pub fn prompt(
+    store: &dyn ProjectStore,
+    cfg: &ResolvedConfig,
+    project: Option<&str>,
+    format: Option<String>,
+) -> Result<()> {
+    let Ok(projdir) = get_current_projdir(store, cfg, project) else {
+        return Ok(());
+    };
+    let name = project_name_for(store, &projdir)?;
+    let branch = git_branch(&projdir);
+    let running = session_marked_running(&name);
+
+    let segment = match format {
+        Some(fmt) => fmt
+            .replace("{name}", &name)
+            .replace("{branch}", branch.as_deref().unwrap_or(""))
+            .replace("{servers}", if running { "servers" } else { "" }),
+        None => {
+            let mut s = name;
+            if let Some(b) = branch {
+                s.push_str(&format!(" ({b})"));
+            }
+            if running {
+                s.push_str(" [servers]");
+            }
+            s
+        }
+    };
+    println!("{}", segment);
+    Ok(())
+}
+
+fn write_state(path: &Path) -> Result<()> {
     let state_path = get_state();
     if let Some(parent) = state_path.parent() {
-        std::fs::create_dir_all(parent)?;
+        fs::create_dir_all(parent)?;
     }
-    std::fs::write(&state_path, path.display().to_string())?;
-    println!("Switched to project '{}' ({})", name, path.display());
+    fs::write(&state_path, path.display().to_string())?;
     Ok(())
 }
 
-pub fn list() -> Result<()> {
-    let projects = load_projects()?;
+pub fn list(
+    store: &dyn ProjectStore,
+    cfg: &ResolvedConfig,
+    tags: &[String],
+    archived: bool,
+) -> Result<()> {
+    let projects = store.load()?;
+    let shown: Vec<(&String, &ProjectEntry)> = projects
+        .ordered()
+        .into_iter()
+        .filter(|(_, e)| e.has_tags(tags) && (archived || !e.archived))
+        .collect();
 
-    let current_path = get_current_projdir().ok().map(|p| canon(&p));
+    let current_path = get_current_projdir(store, cfg, None)
+        .ok()
+        .map(|p| canon(&p));
 
     // Compute padding for alignment
-    let max_name = projects.projects.keys().map(|s| s.len()).max().unwrap_or(0);
+    let max_name = shown.iter().map(|(s, _)| s.len()).max().unwrap_or(0);
     let max_name = cmp::min(max_name, 40);
+    let max_tags = shown
+        .iter()
+        .map(|(_, e)| tags_label(e).len())
+        .max()
+        .unwrap_or(0);
 
     if projects.projects.is_empty() {
         println!("{}", style("no projects added yet").dim());
+    } else if shown.is_empty() && !tags.is_empty() {
+        println!(
+            "{}",
+            style(format!("no projects tagged {}", tags.join(", "))).dim()
+        );
+    } else if shown.is_empty() {
+        println!("{}", style("all projects are archived").dim());
     } else {
         let dot = Emoji("●", "*");
-        for (name, path) in &projects.projects {
+        for (name, entry) in shown {
+            let path = &entry.path;
             let abs = canon(Path::new(path));
             let is_current = current_path.as_ref() == Some(&abs);
+            let missing = if entry.archived {
+                style(" (archived)".to_string()).dim()
+            } else if entry.exists() {
+                style(String::new())
+            } else {
+                style(" (missing)".to_string()).red()
+            };
 
             let left = if is_current {
                 format!("{} {}", style(dot).green(), style(name).bold().green())
@@ -261,7 +718,22 @@ pub fn list() -> Result<()> {
                 style(path).dim()
             };
 
-            println!("{:<width$} {}", left, right, width = max_name + 3);
+            let note = if has_note(name) {
+                style(Emoji("✎", "n").to_string()).yellow()
+            } else {
+                style(" ".to_string())
+            };
+
+            println!(
+                "{:<width$} {} {:<tags_width$} {}{}",
+                left,
+                note,
+                style(tags_label(entry)).cyan(),
+                right,
+                missing,
+                width = max_name + 3,
+                tags_width = max_tags
+            );
         }
     }
 
@@ -270,42 +742,804 @@ pub fn list() -> Result<()> {
         style("Hint:").bold().dim(),
         style(format!(
             "run `projctl add` to add auto-detected projects from `{}`.",
-            get_projects_dir().display()
+            cfg.projects_dir.display()
         ))
         .dim()
     );
+    let hidden = projects.projects.values().filter(|e| e.archived).count();
+    if hidden > 0 && !archived {
+        println!(
+            "{} {}",
+            style("Hint:").bold().dim(),
+            style(format!(
+                "{hidden} archived project(s) hidden; pass `--archived` to show them."
+            ))
+            .dim()
+        );
+    }
+    if projects
+        .projects
+        .values()
+        .any(|e| !e.archived && !e.exists())
+    {
+        println!(
+            "{} {}",
+            style("Hint:").bold().dim(),
+            style("run `projctl relink` to find moved projects or `projctl prune` to drop them.")
+                .dim()
+        );
+    }
 
     Ok(())
 }
 
-pub fn remove(name: String) -> Result<()> {
-    let mut projects = load_projects()?;
-    if projects.projects.remove(&name).is_none() {
-        anyhow::bail!("Project '{}' not found", name);
+/// Drop registry entries whose folder no longer exists.
+pub fn prune(store: &dyn ProjectStore, dry_run: bool) -> Result<()> {
+    let stale: Vec<(String, String)> = store
+        .load()?
+        .projects
+        .into_iter()
+        .filter(|(_, e)| !e.archived && !e.exists())
+        .map(|(n, e)| (n, e.path))
+        .collect();
+    if stale.is_empty() {
+        println!("Nothing to prune.");
+        return Ok(());
+    }
+    for (name, path) in &stale {
+        let verb = if dry_run { "Would remove" } else { "Removing" };
+        println!("{} '{}' ({})", verb, name, path);
     }
-    save_projects(&projects)?;
-    if projects.current.as_deref() == Some(&name) {
-        let _ = fs::remove_file(get_current_projdir()?);
+    if dry_run {
+        return Ok(());
+    }
+
+    let (removed, cleared_current) = store.update(|projects| {
+        let mut removed = 0;
+        let mut cleared_current = false;
+        for (name, _) in &stale {
+            // Re-check under the lock in case it was relinked meanwhile
+            if projects.projects.get(name).is_some_and(|e| !e.exists()) {
+                projects.projects.remove(name);
+                projects.forget(name);
+                removed += 1;
+                if projects.current.as_deref() == Some(name) {
+                    projects.current = None;
+                    cleared_current = true;
+                }
+            }
+        }
+        Ok((removed, cleared_current))
+    })?;
+    if cleared_current {
+        let _ = fs::remove_file(get_state());
+    }
+    println!("Pruned {} project(s).", removed);
+    Ok(())
+}
+
+/// For projects whose folder is gone, look under the scan roots for a repo
+/// with the same `origin` remote (or, with no remote recorded, a folder with
+/// the same name) and offer to point the entry at it.
+pub fn relink(store: &dyn ProjectStore, cfg: &ResolvedConfig, name: Option<String>) -> Result<()> {
+    let projects = store.load()?;
+    let targets: Vec<(String, ProjectEntry)> = match name {
+        Some(n) => {
+            let n = resolve_project(&projects, &n)?.name;
+            vec![(n.clone(), projects.projects[&n].clone())]
+        }
+        None => {
+            let mut v: Vec<_> = projects
+                .projects
+                .iter()
+                .filter(|(_, e)| !e.archived && !e.exists())
+                .map(|(n, e)| (n.clone(), e.clone()))
+                .collect();
+            v.sort_by(|a, b| a.0.cmp(&b.0));
+            v
+        }
+    };
+    if targets.is_empty() {
+        println!("All project paths exist; nothing to relink.");
+        return Ok(());
+    }
+
+    let tracked: Vec<PathBuf> = projects
+        .projects
+        .values()
+        .filter(|e| e.exists())
+        .map(|e| canon(Path::new(&e.path)))
+        .collect();
+    // (folder name, normalized remote, path)
+    let mut candidates: Vec<(String, Option<String>, PathBuf)> = autodetected_projects(cfg)
+        .into_iter()
+        .filter(|(_, p)| !tracked.contains(&canon(p)))
+        .map(|(folder, p)| {
+            let remote = git_remote_url(&p).map(|url| normalize_remote(&url));
+            (folder, remote, p)
+        })
+        .collect();
+
+    for (name, entry) in targets {
+        // Entries without a remote (e.g. migrated from v1) match by folder name
+        let (wanted, matches): (String, Vec<&PathBuf>) = match entry.remote.as_deref() {
+            Some(remote) => {
+                let remote = normalize_remote(remote);
+                let matches = candidates
+                    .iter()
+                    .filter(|(_, url, _)| url.as_deref() == Some(remote.as_str()))
+                    .map(|(_, _, p)| p)
+                    .collect();
+                (format!("repo with remote {remote}"), matches)
+            }
+            None => {
+                let Some(folder) = Path::new(&entry.path).file_name() else {
+                    println!("'{}': no git remote or folder name to search for.", name);
+                    continue;
+                };
+                let folder = folder.to_string_lossy();
+                let matches = candidates
+                    .iter()
+                    .filter(|(f, _, _)| *f == folder)
+                    .map(|(_, _, p)| p)
+                    .collect();
+                (format!("folder named {folder}"), matches)
+            }
+        };
+
+        let new_path = match matches.as_slice() {
+            [] => {
+                let roots: Vec<&str> = cfg.scan_roots.iter().map(|r| r.path.as_str()).collect();
+                println!("'{}': no {} under {}", name, wanted, roots.join(", "));
+                continue;
+            }
+            [only] => {
+                let ok = Confirm::new()
+                    .with_prompt(format!(
+                        "Relink '{}' {} -> {}?",
+                        name,
+                        entry.path,
+                        only.display()
+                    ))
+                    .default(true)
+                    .interact()?;
+                if !ok {
+                    continue;
+                }
+                (*only).clone()
+            }
+            many => {
+                let labels: Vec<String> = many.iter().map(|p| p.display().to_string()).collect();
+                let idx = Select::with_theme(&ColorfulTheme::default())
+                    .with_prompt(format!("Relink '{}' to", name))
+                    .items(&labels)
+                    .default(0)
+                    .interact()?;
+                many[idx].clone()
+            }
+        };
+
+        // Each folder can only be relinked once
+        candidates.retain(|(_, _, p)| *p != new_path);
+
+        // Another projctl may have added the folder since the scan
+        let taken_by = store.update(|projects| {
+            if let Some((other, _)) = projects
+                .projects
+                .iter()
+                .find(|(n, e)| **n != name && same_path(Path::new(&e.path), &new_path))
+            {
+                return Ok(Some(other.clone()));
+            }
+            let entry = projects
+                .projects
+                .get_mut(&name)
+                .ok_or_else(|| anyhow!("Project '{}' not found", name))?;
+            entry.path = new_path.display().to_string();
+            Ok(None)
+        })?;
+        if let Some(other) = taken_by {
+            println!(
+                "'{}': {} is already tracked as '{}'; not relinked.",
+                name,
+                new_path.display(),
+                other
+            );
+            continue;
+        }
+        if fs::read_to_string(get_state())
+            .is_ok_and(|cur| same_path(Path::new(cur.trim()), Path::new(&entry.path)))
+        {
+            write_state(&new_path)?;
+        }
+        println!("Relinked '{}' -> {}", name, new_path.display());
+    }
+    Ok(())
+}
+
+pub fn remove(store: &dyn ProjectStore, name: String) -> Result<()> {
+    let resolved = resolve_project(&store.load()?, &name)?;
+    let name = resolved.name;
+    if resolved.kind != MatchKind::Exact
+        && !Confirm::new()
+            .with_prompt(format!("Remove project '{}'?", name))
+            .default(false)
+            .interact()?
+    {
+        println!("Nothing removed.");
+        return Ok(());
+    }
+    let (was_current, path) = store.update(|projects| {
+        let Some(entry) = projects.projects.remove(&name) else {
+            anyhow::bail!("Project '{}' not found", name);
+        };
+        projects.forget(&name);
+        let was_current = projects.current.as_deref() == Some(&name);
+        if was_current {
+            projects.current = None;
+        }
+        Ok((was_current, entry.path))
+    })?;
+    // The state file may point at it even when `current` doesn't
+    if was_current
+        || fs::read_to_string(get_state())
+            .is_ok_and(|cur| same_path(Path::new(cur.trim()), Path::new(&path)))
+    {
+        let _ = fs::remove_file(get_state());
     }
     println!("Removed project '{}'", name);
+    // Left behind, the note would turn up again for a new project of that name
+    if note_path(&name).exists() {
+        fs::remove_file(note_path(&name)).context("removing project note")?;
+        println!("Removed its note");
+    }
     Ok(())
 }
 
-pub fn path_cmd(name: Option<String>) -> Result<()> {
+pub fn rename(store: &dyn ProjectStore, old: String, new: String) -> Result<()> {
+    let old = resolve_project(&store.load()?, &old)?.name;
+    check_name(&new)?;
+    store.update(|projects| {
+        if let Some(taken) = resolve_exact(projects, &new) {
+            anyhow::bail!("'{}' already refers to project '{}'", new, taken.name);
+        }
+        let entry = projects
+            .projects
+            .remove(&old)
+            .ok_or_else(|| anyhow!("Project '{}' not found", old))?;
+        projects.projects.insert(new.clone(), entry);
+        if projects.current.as_deref() == Some(&old) {
+            projects.current = Some(new.clone());
+        }
+        for n in projects
+            .history
+            .iter_mut()
+            .chain(projects.stack.iter_mut())
+            .filter(|n| **n == old)
+        {
+            *n = new.clone();
+        }
+        Ok(())
+    })?;
+    println!("Renamed project '{}' -> '{}'", old, new);
+
+    let (old_note, new_note) = (note_path(&old), note_path(&new));
+    if old_note.exists() {
+        if new_note.exists() {
+            eprintln!(
+                "{} {} already exists; the note stays at {}",
+                style("warning:").yellow(),
+                new_note.display(),
+                old_note.display()
+            );
+        } else {
+            fs::rename(&old_note, &new_note).context("moving project note")?;
+        }
+    }
+
+    if rename_session(&old, &new)? {
+        println!(
+            "Renamed tmux session '{}' -> '{}'",
+            session_name(&old),
+            session_name(&new)
+        );
+    }
+    Ok(())
+}
+
+/// Point a project at a new folder. If the old folder still exists and the
+/// new one doesn't, the folder itself is moved too.
+pub fn move_proj(store: &dyn ProjectStore, name: String, new_path: String) -> Result<()> {
+    let projects = store.load()?;
+    let name = resolve_project(&projects, &name)?.name;
+    let old_path = PathBuf::from(&projects.projects[&name].path);
+    let new_path = canon(Path::new(&new_path));
+
+    if let Some((other, _)) = projects
+        .projects
+        .iter()
+        .find(|(n, e)| **n != name && same_path(Path::new(&e.path), &new_path))
+    {
+        anyhow::bail!("That path is already tracked as '{}'.", other);
+    }
+
+    if old_path.exists() && !new_path.exists() {
+        if let Some(parent) = new_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&old_path, &new_path)
+            .with_context(|| format!("moving {} to {}", old_path.display(), new_path.display()))?;
+        println!("Moved {} -> {}", old_path.display(), new_path.display());
+    } else if !new_path.exists() {
+        anyhow::bail!(
+            "Neither {} nor {} exists",
+            old_path.display(),
+            new_path.display()
+        );
+    }
+
+    store.update(|projects| {
+        let entry = projects
+            .projects
+            .get_mut(&name)
+            .ok_or_else(|| anyhow!("Project '{}' not found", name))?;
+        entry.path = new_path.display().to_string();
+        Ok(())
+    })?;
+
+    let state_path = get_state();
+    if fs::read_to_string(&state_path).is_ok_and(|cur| same_path(Path::new(cur.trim()), &old_path))
+    {
+        write_state(&new_path)?;
+    }
+    println!("Project '{}' now points to {}", name, new_path.display());
+
+    if session_running(&name) {
+        let restart = Confirm::new()
+            .with_prompt(format!(
+                "tmux session '{}' is still running from the old path. Kill it?",
+                session_name(&name)
+            ))
+            .default(true)
+            .interact()?;
+        if restart {
+            kill_session(&name)?;
+            println!(
+                "Killed '{}'. Run `projctl servers` to recreate it.",
+                session_name(&name)
+            );
+        }
+    }
+    Ok(())
+}
+
+fn tags_label(entry: &ProjectEntry) -> String {
+    if entry.tags.is_empty() {
+        String::new()
+    } else {
+        format!("[{}]", entry.tags.join(", "))
+    }
+}
+
+pub fn tag(store: &dyn ProjectStore, action: TagCommands) -> Result<()> {
+    match action {
+        TagCommands::Add { project, tag } => {
+            let project = resolve_project(&store.load()?, &project)?.name;
+            let tag = tag.trim().to_string();
+            if tag.is_empty() || tag.contains([',', ' ']) {
+                anyhow::bail!(
+                    "Invalid tag '{}': tags can't be empty or contain spaces/commas",
+                    tag
+                );
+            }
+            let added = store.update(|projects| {
+                let entry = projects
+                    .projects
+                    .get_mut(&project)
+                    .ok_or_else(|| anyhow!("Project '{}' not found", project))?;
+                if entry.tags.contains(&tag) {
+                    return Ok(false);
+                }
+                entry.tags.push(tag.clone());
+                entry.tags.sort();
+                Ok(true)
+            })?;
+            if added {
+                println!("Tagged '{}' with '{}'", project, tag);
+            } else {
+                println!("'{}' is already tagged '{}'", project, tag);
+            }
+        }
+        TagCommands::Rm { project, tag } => {
+            let project = resolve_project(&store.load()?, &project)?.name;
+            // Stored tags are trimmed by `tag add`
+            let tag = tag.trim().to_string();
+            store.update(|projects| {
+                let entry = projects
+                    .projects
+                    .get_mut(&project)
+                    .ok_or_else(|| anyhow!("Project '{}' not found", project))?;
+                let before = entry.tags.len();
+                entry.tags.retain(|t| t != &tag);
+                if entry.tags.len() == before {
+                    anyhow::bail!("Project '{}' is not tagged '{}'", project, tag);
+                }
+                Ok(())
+            })?;
+            println!("Removed tag '{}' from '{}'", tag, project);
+        }
+    }
+    Ok(())
+}
+
+/// Archive or unarchive a project. Archived projects keep their registry
+/// entry but are hidden from `list`, the `use` picker and fuzzy matching.
+pub fn archive(store: &dyn ProjectStore, name: String, archived: bool) -> Result<()> {
+    let name = resolve_project_in(&store.load()?, &name, true)?.name;
+    store.update(|projects| {
+        let entry = projects
+            .projects
+            .get_mut(&name)
+            .ok_or_else(|| anyhow!("Project '{}' not found", name))?;
+        entry.archived = archived;
+        Ok(())
+    })?;
+    if archived {
+        println!("Archived '{}'", name);
+    } else {
+        println!("Unarchived '{}'", name);
+    }
+    Ok(())
+}
+
+/// Pin or unpin a project so it sorts first in the `use` picker.
+pub fn pin(store: &dyn ProjectStore, name: String, pinned: bool) -> Result<()> {
+    let name = resolve_project(&store.load()?, &name)?.name;
+    store.update(|projects| {
+        let entry = projects
+            .projects
+            .get_mut(&name)
+            .ok_or_else(|| anyhow!("Project '{}' not found", name))?;
+        entry.pinned = pinned;
+        Ok(())
+    })?;
+    if pinned {
+        println!("Pinned '{}'", name);
+    } else {
+        println!("Unpinned '{}'", name);
+    }
+    Ok(())
+}
+
+pub fn alias(store: &dyn ProjectStore, action: AliasCommands) -> Result<()> {
+    match action {
+        AliasCommands::Add { project, alias } => {
+            let alias = alias.trim().to_string();
+            if alias.is_empty() || alias.contains(char::is_whitespace) || alias == "-" {
+                anyhow::bail!("Invalid alias '{}'", alias);
+            }
+            let project = resolve_project(&store.load()?, &project)?.name;
+            store.update(|projects| {
+                if let Some(taken) = resolve_exact(projects, &alias) {
+                    anyhow::bail!("'{}' already refers to project '{}'", alias, taken.name);
+                }
+                let entry = projects
+                    .projects
+                    .get_mut(&project)
+                    .ok_or_else(|| anyhow!("Project '{}' not found", project))?;
+                entry.aliases.push(alias.clone());
+                entry.aliases.sort();
+                Ok(())
+            })?;
+            println!("'{}' is now an alias for '{}'", alias, project);
+        }
+        AliasCommands::Rm { project, alias } => {
+            let project = resolve_project(&store.load()?, &project)?.name;
+            store.update(|projects| {
+                let entry = projects
+                    .projects
+                    .get_mut(&project)
+                    .ok_or_else(|| anyhow!("Project '{}' not found", project))?;
+                let before = entry.aliases.len();
+                entry.aliases.retain(|a| a != &alias);
+                if entry.aliases.len() == before {
+                    anyhow::bail!("'{}' is not an alias of '{}'", alias, project);
+                }
+                Ok(())
+            })?;
+            println!("Removed alias '{}' from '{}'", alias, project);
+        }
+    }
+    Ok(())
+}
+
+pub fn export(
+    store: &dyn ProjectStore,
+    cfg: &ResolvedConfig,
+    format: ExportFormat,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let export = Export::from_projects(&store.load()?, &cfg.projects_dir);
+    let text = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&export)?,
+        ExportFormat::Toml => toml::to_string_pretty(&export)?,
+    };
+    match output {
+        Some(path) => {
+            fs::write(&path, text).with_context(|| format!("writing {}", path.display()))?;
+            eprintln!(
+                "Exported {} project(s) to {}",
+                export.projects.len(),
+                path.display()
+            );
+        }
+        None => println!("{}", text),
+    }
+    Ok(())
+}
+
+pub fn import(
+    store: &dyn ProjectStore,
+    cfg: &ResolvedConfig,
+    file: PathBuf,
+    on_conflict: ConflictStrategy,
+) -> Result<()> {
+    let text = fs::read_to_string(&file).with_context(|| format!("reading {}", file.display()))?;
+    let parsed: Result<Export> = match file.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(&text).map_err(Into::into),
+        Some("json") => serde_json::from_str(&text).map_err(Into::into),
+        _ => serde_json::from_str(&text)
+            .map_err(anyhow::Error::from)
+            .or_else(|_| toml::from_str(&text).map_err(Into::into)),
+    };
+    let export = parsed.with_context(|| format!("parsing {}", file.display()))?;
+    if export.version > EXPORT_VERSION {
+        anyhow::bail!(
+            "{} uses export version {}, but this projctl only understands up to {}",
+            file.display(),
+            export.version,
+            EXPORT_VERSION
+        );
+    }
+
+    let projects_dir = cfg.projects_dir.as_path();
+    let mut missing = vec![];
+    let mut imported = 0;
+    // (old path, new path) of overwritten entries
+    let mut moved = vec![];
+    store.update(|projects| {
+        for (name, exported) in export.projects {
+            if check_name(&name).is_err() {
+                println!("Skipping '{}': invalid project name.", name);
+                continue;
+            }
+            let mut entry = exported.into_entry(projects_dir);
+
+            let path_owner = projects
+                .projects
+                .iter()
+                .find(|(_, e)| same_path(Path::new(&e.path), Path::new(&entry.path)))
+                .map(|(n, _)| n.clone());
+            match path_owner {
+                Some(owner) if owner == name && on_conflict == ConflictStrategy::Overwrite => {}
+                Some(owner) if owner == name => {
+                    println!("Skipping '{}': already exists.", name);
+                    continue;
+                }
+                Some(owner) => {
+                    println!(
+                        "Skipping '{}': {} is already tracked as '{}'.",
+                        name, entry.path, owner
+                    );
+                    continue;
+                }
+                None => {}
+            }
+
+            let taken = resolve_exact(projects, &name);
+            let name = match (taken, on_conflict) {
+                (None, _) => name,
+                (Some(t), ConflictStrategy::Skip | ConflictStrategy::Overwrite)
+                    if t.kind == MatchKind::Alias =>
+                {
+                    println!("Skipping '{}': already an alias of '{}'.", name, t.name);
+                    continue;
+                }
+                (Some(_), ConflictStrategy::Skip) => {
+                    println!("Skipping '{}': already exists.", name);
+                    continue;
+                }
+                (Some(_), ConflictStrategy::Overwrite) => {
+                    if let Some(old) = projects.projects.remove(&name) {
+                        moved.push((old.path, entry.path.clone()));
+                    }
+                    name
+                }
+                (Some(_), ConflictStrategy::Rename) => {
+                    let renamed = (2..)
+                        .map(|i| format!("{name}-{i}"))
+                        .find(|n| resolve_exact(projects, n).is_none())
+                        .expect("unbounded range");
+                    println!("Importing '{}' as '{}'.", name, renamed);
+                    renamed
+                }
+            };
+
+            entry.aliases.retain(|a| {
+                let taken = resolve_exact(projects, a).is_some_and(|r| r.name != name);
+                if taken {
+                    println!("Dropping alias '{}' of '{}': already in use.", a, name);
+                }
+                !taken
+            });
+            if !entry.exists() {
+                missing.push((name.clone(), entry.path.clone(), entry.remote.clone()));
+            }
+            projects.projects.insert(name, entry);
+            imported += 1;
+        }
+        Ok(())
+    })?;
+    // An overwritten entry may be the one the state file points at
+    if let Ok(cur) = fs::read_to_string(get_state())
+        && let Some((_, new)) = moved
+            .iter()
+            .find(|(old, _)| same_path(Path::new(cur.trim()), Path::new(old)))
+    {
+        write_state(Path::new(new))?;
+    }
+
+    println!("Imported {} project(s).", imported);
+    if !missing.is_empty() {
+        missing.sort();
+        println!("\n{}", style("These paths don't exist yet:").yellow());
+        for (name, path, remote) in missing {
+            match remote {
+                Some(url) => println!("  {name}  {path}  (git clone {url} {path})"),
+                None => println!("  {name}  {path}"),
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn path_cmd(
+    store: &dyn ProjectStore,
+    cfg: &ResolvedConfig,
+    project: Option<&str>,
+    name: Option<String>,
+    tags: &[String],
+    archived: bool,
+) -> Result<()> {
     if let Some(n) = name {
-        let projdir = get_projdir(&n)?.ok_or_else(|| anyhow!("Project '{}' not found", n))?;
-        println!("{}", projdir.display());
+        let projects = store.load()?;
+        let resolved = resolve_project_in(&projects, &n, archived)?;
+        let entry = &projects.projects[&resolved.name];
+        if !entry.has_tags(tags) {
+            anyhow::bail!(
+                "Project '{}' is not tagged {}",
+                resolved.name,
+                tags.join(", ")
+            );
+        }
+        println!("{}", entry.path);
+    } else if !tags.is_empty() {
+        let projects = store.load()?;
+        if let Some((_, path)) =
+            pick_project(store, cfg, &projects, tags, archived, "Select project")?
+        {
+            println!("{}", path);
+        }
     } else {
-        let projdir = get_current_projdir()?;
+        let projdir = get_current_projdir(store, cfg, project)?;
         println!("{}", projdir.display());
     }
     Ok(())
 }
 
-pub fn run(projdir: PathBuf, cmd: Vec<String>) -> Result<()> {
+pub fn which_proj(store: &dyn ProjectStore, path: Option<PathBuf>) -> Result<()> {
+    let path = match path {
+        Some(p) => p,
+        None => env::current_dir()?,
+    };
+    let projects = store.load()?;
+    match project_containing(&projects, &path) {
+        Some((name, _)) => {
+            println!("{}", name);
+            Ok(())
+        }
+        None => anyhow::bail!("{} is not inside a registered project", path.display()),
+    }
+}
+
+/// `projctl cd`: the project folder (or `subdir` inside it), as a `cd` line
+/// for the shell wrapper or as a bare path otherwise.
+pub fn cd_proj(
+    store: &dyn ProjectStore,
+    cfg: &ResolvedConfig,
+    project: Option<&str>,
+    name: Option<String>,
+    subdir: Option<PathBuf>,
+    emit: Option<Shell>,
+) -> Result<()> {
+    let projdir = get_current_projdir(store, cfg, name.as_deref().or(project))?;
+    let target = match subdir {
+        Some(sub) => projdir.join(sub),
+        None => projdir,
+    };
+    if !target.is_dir() {
+        anyhow::bail!("{} is not a directory", target.display());
+    }
+    match emit {
+        Some(shell) => println!("{}", cd_line(shell, &target)),
+        None => println!("{}", target.display()),
+    }
+    Ok(())
+}
+
+/// Candidates for the completion functions in `shell-init`. Never prompts.
+pub fn complete(
+    store: &dyn ProjectStore,
+    cfg: &ResolvedConfig,
+    what: CompleteCommands,
+) -> Result<()> {
+    let projects = store.load()?;
+    match what {
+        CompleteCommands::Projects => {
+            let mut names: Vec<&str> = projects
+                .projects
+                .iter()
+                .filter(|(_, e)| !e.archived)
+                .flat_map(|(n, e)| std::iter::once(n).chain(e.aliases.iter()))
+                .map(String::as_str)
+                .collect();
+            let auto = autodetected_projects(cfg);
+            names.extend(auto.iter().map(|(n, _)| n.as_str()));
+            names.sort();
+            names.dedup();
+            for n in names {
+                println!("{}", n);
+            }
+        }
+        CompleteCommands::Subdirs { project, prefix } => {
+            let projdir = match resolve_exact(&projects, &project) {
+                Some(r) => Some(PathBuf::from(&projects.projects[&r.name].path)),
+                None => get_autodetected_projdir(cfg, &project),
+            };
+            let Some(projdir) = projdir else {
+                return Ok(());
+            };
+            let (parent, partial) = match prefix.rfind('/') {
+                Some(i) => prefix.split_at(i + 1),
+                None => ("", prefix.as_str()),
+            };
+            let Ok(entries) = fs::read_dir(projdir.join(parent)) else {
+                return Ok(());
+            };
+            let mut dirs: Vec<String> = entries
+                .flatten()
+                .filter(|e| e.path().is_dir())
+                .filter_map(|e| e.file_name().into_string().ok())
+                .filter(|n| {
+                    n.starts_with(partial) && (partial.starts_with('.') || !n.starts_with('.'))
+                })
+                .map(|n| format!("{parent}{n}/"))
+                .collect();
+            dirs.sort();
+            for d in dirs {
+                println!("{}", d);
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn run(store: &dyn ProjectStore, projdir: PathBuf, cmd: Vec<String>) -> Result<()> {
+    let vars = project_env(store, &projdir)?;
     env::set_current_dir(&projdir)?;
     let output = Command::new(&cmd[0])
         .args(&cmd[1..])
+        .envs(&vars)
         .current_dir(&projdir)
         .output()?;
     if !output.status.success() {
@@ -315,33 +1549,253 @@ pub fn run(projdir: PathBuf, cmd: Vec<String>) -> Result<()> {
     Ok(())
 }
 
-pub fn edit(projdir: PathBuf, cfg: &ResolvedConfig) -> Result<()> {
-    let (bin, args) = parse_cmd(&cfg.editor);
-    Command::new(bin)
+/// Open the current project's note in the editor, or append `text` to it.
+pub fn note(
+    store: &dyn ProjectStore,
+    projdir: PathBuf,
+    text: Vec<String>,
+    cfg: &ResolvedConfig,
+) -> Result<()> {
+    let name = project_name_for(store, &projdir)?;
+    let path = note_path(&name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    if !text.is_empty() {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("opening {}", path.display()))?;
+        writeln!(file, "{}", text.join(" "))?;
+        println!("Added note to '{}'", name);
+        return Ok(());
+    }
+
+    if !path.exists() {
+        fs::write(&path, format!("# {name}\n\n"))?;
+    }
+    let note = path.display().to_string();
+    let (bin, args) = expand_cmd(
+        cfg.editor.cmd(),
+        &[
+            ("path", Some(&projdir.display().to_string())),
+            ("file", Some(&note)),
+            ("name", Some(&name)),
+        ],
+        &[&note],
+    );
+    Command::new(&bin)
+        .args(&args)
+        .current_dir(&projdir)
+        .status()
+        .with_context(|| tool_failed("editor", &bin, &cfg.editor))?;
+    Ok(())
+}
+
+pub fn link(store: &dyn ProjectStore, projdir: PathBuf, action: LinkCommands) -> Result<()> {
+    let name = project_name_for(store, &projdir)?;
+    match action {
+        LinkCommands::Add { label, url } => {
+            store.update(|projects| {
+                let entry = projects
+                    .projects
+                    .get_mut(&name)
+                    .ok_or_else(|| anyhow!("Project '{}' is not added", name))?;
+                entry.links.insert(label.clone(), url.clone());
+                Ok(())
+            })?;
+            println!("Added link '{}' to '{}'", label, name);
+        }
+        LinkCommands::Rm { label } => {
+            store.update(|projects| {
+                let entry = projects
+                    .projects
+                    .get_mut(&name)
+                    .ok_or_else(|| anyhow!("Project '{}' is not added", name))?;
+                if entry.links.remove(&label).is_none() {
+                    anyhow::bail!("Project '{}' has no link '{}'", name, label);
+                }
+                Ok(())
+            })?;
+            println!("Removed link '{}' from '{}'", label, name);
+        }
+        LinkCommands::List => {
+            let projects = store.load()?;
+            let links = projects.projects.get(&name).map(|e| &e.links);
+            match links {
+                Some(links) if !links.is_empty() => {
+                    let width = links.keys().map(|k| k.len()).max().unwrap_or(0);
+                    for (label, url) in links {
+                        println!("{:<width$}  {}", label, style(url).dim());
+                    }
+                }
+                _ => println!("{}", style("no links yet").dim()),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Open one of the current project's links in the browser.
+pub fn browse(
+    store: &dyn ProjectStore,
+    projdir: PathBuf,
+    cfg: &ResolvedConfig,
+    label: Option<String>,
+) -> Result<()> {
+    let name = project_name_for(store, &projdir)?;
+    let links = store
+        .load()?
+        .projects
+        .remove(&name)
+        .map(|e| e.links)
+        .unwrap_or_default();
+    if links.is_empty() {
+        anyhow::bail!(
+            "Project '{}' has no links. Hint: `projctl link add <name> <url>`",
+            name
+        );
+    }
+
+    let url = match label {
+        Some(label) => links
+            .get(&label)
+            .cloned()
+            .ok_or_else(|| anyhow!("Project '{}' has no link '{}'", name, label))?,
+        None if links.len() == 1 => links.values().next().cloned().unwrap(),
+        None => {
+            let items: Vec<(&String, &String)> = links.iter().collect();
+            let labels: Vec<String> = items.iter().map(|(l, u)| format!("{l}    {u}")).collect();
+            let idx = Select::with_theme(&ColorfulTheme::default())
+                .with_prompt("Open link")
+                .items(&labels)
+                .default(0)
+                .interact()?;
+            items[idx].1.clone()
+        }
+    };
+
+    let (bin, args) = expand_cmd(
+        cfg.browser.cmd(),
+        &[
+            ("path", Some(&projdir.display().to_string())),
+            ("name", Some(&name)),
+            ("url", Some(&url)),
+        ],
+        &[&url],
+    );
+    Command::new(&bin)
+        .args(&args)
+        .status()
+        .with_context(|| tool_failed("browser", &bin, &cfg.browser))?;
+    Ok(())
+}
+
+/// Open the editor on the project root, or on `target` (`file[:line]`,
+/// relative to the project root).
+pub fn edit(
+    store: &dyn ProjectStore,
+    projdir: PathBuf,
+    cfg: &ResolvedConfig,
+    target: Option<String>,
+) -> Result<()> {
+    let name = project_name_for(store, &projdir)?;
+    let (file, line) = match &target {
+        Some(t) => match t.rsplit_once(':') {
+            Some((file, line)) if !line.is_empty() && line.bytes().all(|b| b.is_ascii_digit()) => {
+                (file, Some(line))
+            }
+            _ => (t.as_str(), None),
+        },
+        None => (".", None),
+    };
+    if line.is_some() && !cfg.editor.cmd().contains("{line}") {
+        eprintln!(
+            "{} the editor command has no {{line}} placeholder; opening {} at the top",
+            style("warning:").yellow(),
+            file
+        );
+    }
+    let (bin, args) = expand_cmd(
+        cfg.editor.cmd(),
+        &[
+            ("path", Some(&projdir.display().to_string())),
+            ("file", Some(file)),
+            ("line", line),
+            ("name", Some(&name)),
+        ],
+        &[file],
+    );
+    Command::new(&bin)
         .args(&args)
-        .arg(".")
+        .envs(project_env(store, &projdir)?)
         .current_dir(&projdir)
-        .status()?;
+        .status()
+        .with_context(|| tool_failed("editor", &bin, &cfg.editor))?;
     Ok(())
 }
 
-pub fn git(projdir: PathBuf, cfg: &ResolvedConfig) -> Result<()> {
-    let (bin, mut args) = parse_cmd(&cfg.git_ui);
-    args.extend(["-p".into(), projdir.display().to_string()]);
-    Command::new(bin)
+/// Open the project root in the file manager.
+pub fn open(store: &dyn ProjectStore, projdir: PathBuf, cfg: &ResolvedConfig) -> Result<()> {
+    let name = project_name_for(store, &projdir)?;
+    let path = projdir.display().to_string();
+    let (bin, args) = expand_cmd(
+        cfg.file_manager.cmd(),
+        &[("path", Some(&path)), ("name", Some(&name))],
+        &[&path],
+    );
+    Command::new(&bin)
         .args(&args)
         .current_dir(&projdir)
-        .status()?;
+        .status()
+        .with_context(|| tool_failed("file manager", &bin, &cfg.file_manager))?;
     Ok(())
 }
 
-pub fn logs(path: Option<String>) -> Result<()> {
+fn tool_failed(label: &str, bin: &str, tool: &Tool) -> String {
+    match tool.candidates.as_slice() {
+        [_] => format!("running {label} '{bin}'"),
+        all => format!(
+            "running {label} '{bin}' (none of {} found on PATH)",
+            all.join(", ")
+        ),
+    }
+}
+
+pub fn git(store: &dyn ProjectStore, projdir: PathBuf, cfg: &ResolvedConfig) -> Result<()> {
+    let name = project_name_for(store, &projdir)?;
+    // Commands without placeholders run from the project root as-is
+    let (bin, args) = expand_cmd(
+        cfg.git_ui.cmd(),
+        &[
+            ("path", Some(&projdir.display().to_string())),
+            ("name", Some(&name)),
+        ],
+        &[],
+    );
+    Command::new(&bin)
+        .args(&args)
+        .envs(project_env(store, &projdir)?)
+        .current_dir(&projdir)
+        .status()
+        .with_context(|| tool_failed("git UI", &bin, &cfg.git_ui))?;
+    Ok(())
+}
+
+pub fn logs(
+    store: &dyn ProjectStore,
+    cfg: &ResolvedConfig,
+    project: Option<&str>,
+    path: Option<String>,
+) -> Result<()> {
     let target = if let Some(p) = path {
         env::current_dir()?.join(p).canonicalize()?
     } else {
-        get_current_projdir()?
+        get_current_projdir(store, cfg, project)?
     };
-    let logdir = target.join("logs");
+    let logdir = target.join(cfg.log_dir.as_deref().unwrap_or("logs"));
     if logdir.exists() && fs::read_dir(&logdir)?.next().is_some() {
         if which::which("lnav").is_ok() {
             Command::new("lnav")
@@ -360,11 +1814,342 @@ pub fn logs(path: Option<String>) -> Result<()> {
     Ok(())
 }
 
-pub fn servers(projdir: PathBuf, refresh: bool, reset: bool, kill: bool) -> Result<()> {
-    setup_servers(&projdir, refresh, reset, kill)
+pub fn servers(
+    store: &dyn ProjectStore,
+    cfg: &ResolvedConfig,
+    projdir: PathBuf,
+    refresh: bool,
+    reset: bool,
+    kill: bool,
+) -> Result<()> {
+    let name = project_name_for(store, &projdir)?;
+    let vars = project_env(store, &projdir)?;
+    setup_servers(&name, &projdir, cfg, &vars, refresh, reset, kill)
+}
+
+/// `projctl hook`: manage the current project's `on_enter`/`on_leave`.
+pub fn hook(store: &dyn ProjectStore, projdir: PathBuf, action: HookCommands) -> Result<()> {
+    let name = project_name_for(store, &projdir)?;
+    let not_added = || {
+        anyhow!(
+            "'{}' is not an added project. Hint: run `projctl add {}` first.",
+            name,
+            name
+        )
+    };
+    match action {
+        HookCommands::Set { event, cmd } => {
+            let cmd = cmd.join(" ");
+            store.update(|projects| {
+                let entry = projects.projects.get_mut(&name).ok_or_else(not_added)?;
+                match event {
+                    HookEvent::Enter => entry.on_enter = Some(cmd),
+                    HookEvent::Leave => entry.on_leave = Some(cmd),
+                }
+                Ok(())
+            })?;
+            println!("Set {} hook for '{}'", event_label(event), name);
+        }
+        HookCommands::Clear { event } => {
+            store.update(|projects| {
+                let entry = projects.projects.get_mut(&name).ok_or_else(not_added)?;
+                if event.is_none_or(|e| e == HookEvent::Enter) {
+                    entry.on_enter = None;
+                }
+                if event.is_none_or(|e| e == HookEvent::Leave) {
+                    entry.on_leave = None;
+                }
+                Ok(())
+            })?;
+            match event {
+                Some(e) => println!("Cleared {} hook for '{}'", event_label(e), name),
+                None => println!("Cleared hooks for '{}'", name),
+            }
+        }
+        // Read-only: no lock-and-rewrite of the registry
+        HookCommands::List => {
+            let projects = store.load()?;
+            let entry = projects.projects.get(&name).ok_or_else(not_added)?;
+            if entry.on_enter.is_none() && entry.on_leave.is_none() {
+                println!("{}", style("no hooks").dim());
+            }
+            for (event, cmd) in [("enter", &entry.on_enter), ("leave", &entry.on_leave)] {
+                if let Some(cmd) = cmd {
+                    println!("{:<6} {}", event, cmd);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn event_label(event: HookEvent) -> &'static str {
+    match event {
+        HookEvent::Enter => "on_enter",
+        HookEvent::Leave => "on_leave",
+    }
 }
 
-pub fn create_db(name: String) -> Result<()> {
+/// `projctl env`: print the project's environment as shell lines to `eval`,
+/// or edit the registry's `env` / `env_files`.
+pub fn env_cmd(
+    store: &dyn ProjectStore,
+    projdir: PathBuf,
+    shell: Shell,
+    action: Option<EnvCommands>,
+) -> Result<()> {
+    let Some(action) = action else {
+        for (k, v) in project_env(store, &projdir)? {
+            println!("{}", export_var(shell, &k, &v)?);
+        }
+        return Ok(());
+    };
+    let name = project_name_for(store, &projdir)?;
+    store.update(|projects| {
+        let Some(entry) = projects.projects.get_mut(&name) else {
+            anyhow::bail!(
+                "'{}' is not an added project. Hint: run `projctl add {}` first.",
+                name,
+                name
+            );
+        };
+        match action {
+            EnvCommands::Set { key, value } => {
+                if !is_var_name(&key) {
+                    anyhow::bail!(
+                        "Invalid variable name '{}' (use letters, digits and _, not starting with a digit)",
+                        key
+                    );
+                }
+                println!("Set {} for '{}'", key, name);
+                entry.env.insert(key, value);
+            }
+            EnvCommands::Unset { key } => {
+                if entry.env.remove(&key).is_none() {
+                    anyhow::bail!("'{}' has no variable {}", name, key);
+                }
+                println!("Unset {} for '{}'", key, name);
+            }
+            EnvCommands::AddFile { file } => {
+                if !entry.env_files.contains(&file) {
+                    if !projdir.join(&file).exists() {
+                        eprintln!(
+                            "warning: {} does not exist yet",
+                            projdir.join(&file).display()
+                        );
+                    }
+                    entry.env_files.push(file.clone());
+                }
+                println!("'{}' now loads {}", name, file);
+            }
+            EnvCommands::RmFile { file } => {
+                let before = entry.env_files.len();
+                entry.env_files.retain(|f| *f != file);
+                if entry.env_files.len() == before {
+                    anyhow::bail!("'{}' does not load {}", name, file);
+                }
+                println!("'{}' no longer loads {}", name, file);
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Registry name of the project at `projdir`, falling back to the folder
+/// name for directories that were switched to without being added.
+fn project_name_for(store: &dyn ProjectStore, projdir: &Path) -> Result<String> {
+    let projects = store.load()?;
+    if let Some((name, _)) = projects
+        .projects
+        .iter()
+        .find(|(_, e)| same_path(Path::new(&e.path), projdir))
+    {
+        return Ok(name.clone());
+    }
+    Ok(projdir
+        .file_name()
+        .ok_or_else(|| anyhow!("{} has no folder name", projdir.display()))?
+        .to_string_lossy()
+        .to_string())
+}
+
+pub fn config_cmd(
+    cfg: &ResolvedConfig,
+    sources: &ConfigSources,
+    projdir: Option<PathBuf>,
+    action: ConfigCommands,
+) -> Result<()> {
+    let file_for = |local: bool| -> Result<(PathBuf, ConfigKind)> {
+        if !local {
+            return Ok((sources.user_path.to_path_buf(), ConfigKind::User));
+        }
+        let dir = projdir
+            .clone()
+            .ok_or_else(|| anyhow!("No current project. Hint: run `projctl use <name>` first."))?;
+        Ok((dir.join(PROJECT_FILE), ConfigKind::Project))
+    };
+    match action {
+        ConfigCommands::Show => {
+            if let Some(name) = sources.profile {
+                println!("{} {}", style("profile:").dim(), style(name).bold());
+            }
+            let settings = cfg.explain(sources);
+            let width = settings.iter().map(|s| s.key.len()).max().unwrap_or(0);
+            for s in settings {
+                let value = if s.value.is_empty() {
+                    style("(none)".to_string()).dim()
+                } else {
+                    style(s.value)
+                };
+                println!(
+                    "{:width$}  {}  {}",
+                    style(s.key).bold(),
+                    value,
+                    style(format!("[{}]", s.source)).dim(),
+                );
+            }
+        }
+        ConfigCommands::Get { key } => {
+            let settings = cfg.explain(sources);
+            let Some(s) = settings.iter().find(|s| s.key == key) else {
+                let keys: Vec<_> = settings.iter().map(|s| s.key).collect();
+                anyhow::bail!("Unknown key '{}'. Known keys: {}", key, keys.join(", "));
+            };
+            println!("{}", s.value);
+        }
+        ConfigCommands::Set { key, value, local } => {
+            let (path, kind) = file_for(local)?;
+            let settable = match kind {
+                ConfigKind::User => SETTABLE_USER_KEYS,
+                ConfigKind::Project => SETTABLE_PROJECT_KEYS,
+            };
+            if !settable.contains(&key.as_str()) {
+                anyhow::bail!(
+                    "Can't set '{}' in {}. Settable keys: {}",
+                    key,
+                    path.display(),
+                    settable.join(", ")
+                );
+            }
+            let text = match fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+                Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+            };
+            // With a profile selected, user settings go into its table
+            let updated = match (kind, sources.profile) {
+                (ConfigKind::User, Some(name)) => {
+                    set_key(&text, &format!("profiles.{name}.{key}"), &value)
+                }
+                _ => set_key(&text, &key, &value),
+            };
+            // Only refuse problems this edit introduced
+            let before: Vec<_> = validate_text(&text, kind)
+                .into_iter()
+                .map(|i| i.message)
+                .collect();
+            let issues = validate_text(&updated, kind);
+            if let Some(issue) = issues.iter().find(|i| !before.contains(&i.message)) {
+                anyhow::bail!(
+                    "Refusing to write {}: line {}: {}",
+                    path.display(),
+                    issue.line,
+                    issue.message
+                );
+            }
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, updated).with_context(|| format!("writing {}", path.display()))?;
+            match (kind, sources.profile) {
+                (ConfigKind::User, Some(name)) => {
+                    println!("Set {} for profile '{}' in {}", key, name, path.display())
+                }
+                _ => println!("Set {} in {}", key, path.display()),
+            }
+        }
+        ConfigCommands::Edit { local } => {
+            let (path, _) = file_for(local)?;
+            if !path.exists() {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&path, "")?;
+            }
+            let file = path.display().to_string();
+            let (bin, args) = expand_cmd(cfg.editor.cmd(), &[("file", Some(&file))], &[&file]);
+            Command::new(&bin)
+                .args(&args)
+                .status()
+                .with_context(|| tool_failed("editor", &bin, &cfg.editor))?;
+        }
+        ConfigCommands::Validate => {
+            let mut files = vec![(sources.user_path.to_path_buf(), ConfigKind::User)];
+            if let Some(dir) = &projdir {
+                files.push((dir.join(PROJECT_FILE), ConfigKind::Project));
+            }
+            let mut failed = false;
+            for (path, kind) in files {
+                let Ok(text) = fs::read_to_string(&path) else {
+                    continue;
+                };
+                let issues = validate_text(&text, kind);
+                if issues.is_empty() {
+                    println!("{} {}", style("ok").green(), path.display());
+                }
+                for issue in issues {
+                    failed = true;
+                    println!(
+                        "{}:{}: {}",
+                        path.display(),
+                        issue.line,
+                        style(issue.message).red()
+                    );
+                }
+            }
+            if failed {
+                anyhow::bail!("config is invalid");
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn profile_cmd(user: &FileConfig, action: ProfileCommands) -> Result<()> {
+    match action {
+        ProfileCommands::List => {
+            let names = std::iter::once(None).chain(user.profiles.keys().map(|n| Some(n.as_str())));
+            for name in names {
+                let db = profile_dir(config_dir(), name).join(PROJECTS_DB);
+                let count = match db.exists() {
+                    true => JsonFileStore::new(&db).load()?.projects.len(),
+                    false => 0,
+                };
+                let marker = match name == profile() {
+                    true => style("*").green().to_string(),
+                    false => " ".to_string(),
+                };
+                println!(
+                    "{} {:12} {} {}",
+                    marker,
+                    style(name.unwrap_or("(default)")).bold(),
+                    style(format!(
+                        "{count} project{}",
+                        if count == 1 { "" } else { "s" }
+                    ))
+                    .dim(),
+                    style(db.display()).dim(),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn create_db(name: Option<String>, cfg: &ResolvedConfig) -> Result<()> {
+    let Some(name) = name.or_else(|| cfg.database.clone()) else {
+        anyhow::bail!("No database name given. Pass one or set `database` in .projctl.toml.");
+    };
     let user = env::var("PGUSER").unwrap_or_else(|_| "postgres".to_string());
     let pass = env::var("PGPASSWORD").unwrap_or_else(|_| "postgres".to_string());
     let host = env::var("PGHOST").unwrap_or_else(|_| "localhost".to_string());
@@ -1,44 +1,304 @@
 use anyhow::{Context, Result};
 use serde::Deserialize;
 use std::{
+    collections::BTreeMap,
     fs,
     path::{Path, PathBuf},
+    sync::OnceLock,
 };
 
-use crate::utils::expand_tilde;
+use crate::utils::{PROJECTS_DIR, config_dir, expand_tilde};
 
 #[derive(Debug, Clone, Deserialize, Default)]
 pub struct FileConfig {
-    pub editor: Option<String>,
-    pub git_ui: Option<String>,
+    pub editor: Option<Candidates>,
+    pub git_ui: Option<Candidates>,
+    pub file_manager: Option<Candidates>,
+    pub browser: Option<Candidates>,
+    pub projects_dir: Option<String>,
+    pub log_dir: Option<String>,
+    #[serde(default)]
+    pub scan_roots: Vec<ScanRoot>,
+    /// `[profiles.<name>]`: settings layered over the ones above when the
+    /// profile is selected.
+    #[serde(default)]
+    pub profiles: BTreeMap<String, FileConfig>,
+}
+
+impl FileConfig {
+    /// These settings with `profile`'s on top. A profile's `scan_roots`
+    /// replace the base list.
+    pub fn for_profile(mut self, profile: Option<&str>) -> Result<Self> {
+        let Some(name) = profile else {
+            return Ok(self);
+        };
+        // The name becomes a folder under profiles/
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            anyhow::bail!("Invalid profile name '{}'", name);
+        }
+        let Some(p) = self.profiles.remove(name) else {
+            let known: Vec<_> = self.profiles.keys().map(String::as_str).collect();
+            anyhow::bail!(
+                "Unknown profile '{}'. Known profiles: {}. Hint: add a [profiles.{}] table to the config.",
+                name,
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                },
+                name
+            );
+        };
+        Ok(Self {
+            editor: p.editor.or(self.editor),
+            git_ui: p.git_ui.or(self.git_ui),
+            file_manager: p.file_manager.or(self.file_manager),
+            browser: p.browser.or(self.browser),
+            projects_dir: p.projects_dir.or(self.projects_dir),
+            log_dir: p.log_dir.or(self.log_dir),
+            scan_roots: if p.scan_roots.is_empty() {
+                self.scan_roots
+            } else {
+                p.scan_roots
+            },
+            profiles: self.profiles,
+        })
+    }
+}
+
+/// A folder searched for auto-detected projects.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScanRoot {
+    pub path: String,
+    /// How many levels below `path` projects may sit. Above the last level,
+    /// only folders with a `.git` or `.projctl.toml` count as projects (and
+    /// are not descended into).
+    #[serde(default = "default_depth")]
+    pub depth: usize,
+    /// Folder name patterns to skip (`*` and `?` wildcards).
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+fn default_depth() -> usize {
+    1
+}
+
+/// A command, or an ordered list of commands to fall back through. `$VAR`
+/// entries take their command from the environment. Commands may use the
+/// placeholders expanded by `utils::expand_cmd`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged, expecting = "expected a command or a list of commands")]
+pub enum Candidates {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Candidates {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            Candidates::One(cmd) => vec![cmd],
+            Candidates::Many(cmds) => cmds,
+        }
+    }
+}
+
+/// An external tool: candidate commands, picked from on first use so
+/// commands that never run it (like the prompt) don't search PATH.
+#[derive(Debug, Clone)]
+pub struct Tool {
+    pub candidates: Vec<String>,
+    picked: OnceLock<String>,
+}
+
+impl Tool {
+    fn new(candidates: Vec<String>) -> Self {
+        Self {
+            candidates,
+            picked: OnceLock::new(),
+        }
+    }
+
+    fn defaults(candidates: &[&str]) -> Self {
+        Self::new(candidates.iter().map(|c| c.to_string()).collect())
+    }
+
+    /// The first candidate whose program is on PATH, or the first candidate
+    /// when none is (so running it reports what's missing).
+    pub fn cmd(&self) -> &str {
+        self.picked.get_or_init(|| {
+            let expanded: Vec<String> = self
+                .candidates
+                .iter()
+                .filter_map(|c| match c.strip_prefix('$') {
+                    // $BROWSER is a colon-separated list by convention
+                    Some("BROWSER") => std::env::var("BROWSER")
+                        .ok()
+                        .and_then(|b| b.split(':').next().map(str::to_string)),
+                    Some(var) => std::env::var(var).ok(),
+                    None => Some(c.clone()),
+                })
+                .filter(|c| !c.trim().is_empty())
+                .collect();
+            expanded
+                .iter()
+                .find(|c| {
+                    shlex::split(c)
+                        .and_then(|parts| parts.into_iter().next())
+                        .is_some_and(|bin| which::which(bin).is_ok())
+                })
+                .or(expanded.first())
+                .cloned()
+                .unwrap_or_default()
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct ResolvedConfig {
-    pub editor: String,
-    pub git_ui: String,
+    pub editor: Tool,
+    pub git_ui: Tool,
+    pub file_manager: Tool,
+    pub browser: Tool,
+    pub projects_dir: PathBuf,
+    /// `projects_dir` first, then the configured `scan_roots`.
+    pub scan_roots: Vec<ScanRoot>,
+    /// Where dev servers write logs, relative to the project root. `None`
+    /// keeps the per-app `logs/` folders.
+    pub log_dir: Option<String>,
+    pub dev: DevCommands,
+    pub windows: Vec<WindowSpec>,
+    pub database: Option<String>,
 }
 
 impl ResolvedConfig {
     pub fn default_setting() -> Self {
         Self {
-            editor: "nvim".to_string(),
-            git_ui: "lazygit".to_string(),
+            editor: Tool::defaults(&[
+                "nvim +{line} {file}",
+                "vim +{line} {file}",
+                "code {file}",
+                "$VISUAL",
+                "$EDITOR",
+            ]),
+            git_ui: Tool::defaults(&["lazygit -p {path}", "gitui", "tig", "git status"]),
+            file_manager: Tool::defaults(&["xdg-open {path}", "open {path}"]),
+            browser: Tool::defaults(&["$BROWSER", "xdg-open {url}", "open {url}"]),
+            projects_dir: expand_tilde(PROJECTS_DIR),
+            scan_roots: Vec::new(),
+            log_dir: None,
+            dev: DevCommands::default(),
+            windows: Vec::new(),
+            database: None,
         }
     }
 
-    /// Merge with precedence: CLI > File > Defaults
-    pub fn resolve(cli: &crate::cli::Cli, file: FileConfig) -> Self {
+    /// Merge with precedence: CLI > Project file > User file > Defaults
+    pub fn resolve(cli: &crate::cli::Cli, file: FileConfig, project: ProjectFile) -> Self {
         let d = Self::default_setting();
+        let projects_dir = file
+            .projects_dir
+            .map(|p| expand_tilde(&p))
+            .unwrap_or(d.projects_dir);
+        let mut scan_roots = vec![ScanRoot {
+            path: projects_dir.display().to_string(),
+            depth: default_depth(),
+            ignore: Vec::new(),
+        }];
+        for root in file.scan_roots {
+            // A scan root repeating projects_dir replaces the default entry
+            if expand_tilde(&root.path) == projects_dir {
+                scan_roots[0] = root;
+            } else {
+                scan_roots.push(root);
+            }
+        }
+        let tool = |cli: &Option<String>,
+                    project: Option<Candidates>,
+                    file: Option<Candidates>,
+                    default: Tool| {
+            Tool::new(
+                cli.clone()
+                    .map(Candidates::One)
+                    .or(project)
+                    .or(file)
+                    .map_or(default.candidates, Candidates::into_vec),
+            )
+        };
         Self {
-            editor: cli.editor.clone().or(file.editor).unwrap_or(d.editor),
-            git_ui: cli.git_ui.clone().or(file.git_ui).unwrap_or(d.git_ui),
+            editor: tool(&cli.editor, project.editor, file.editor, d.editor),
+            git_ui: tool(&cli.git_ui, project.git_ui, file.git_ui, d.git_ui),
+            file_manager: tool(
+                &None,
+                project.file_manager,
+                file.file_manager,
+                d.file_manager,
+            ),
+            browser: tool(&None, project.browser, file.browser, d.browser),
+            projects_dir,
+            scan_roots,
+            log_dir: project.log_dir.or(file.log_dir),
+            dev: project.dev,
+            windows: project.windows,
+            database: project.database,
         }
     }
 }
 
+/// Repo-local settings, read from `.projctl.toml` at the project root.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct ProjectFile {
+    pub editor: Option<Candidates>,
+    pub git_ui: Option<Candidates>,
+    pub file_manager: Option<Candidates>,
+    pub browser: Option<Candidates>,
+    #[serde(default)]
+    pub dev: DevCommands,
+    /// Extra tmux windows for `projctl servers`.
+    #[serde(default)]
+    pub windows: Vec<WindowSpec>,
+    pub log_dir: Option<String>,
+    /// Default name for `projctl db-create`.
+    pub database: Option<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub env_files: Vec<String>,
+}
+
+/// Dev server commands, replacing the detected ones.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct DevCommands {
+    pub frontend: Option<String>,
+    pub backend: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WindowSpec {
+    #[serde(deserialize_with = "window_name")]
+    pub name: String,
+    /// Working directory, relative to the project root.
+    pub dir: Option<String>,
+    /// Command to start in the window.
+    pub cmd: Option<String>,
+}
+
+/// Window names end up in tmux targets (`<session>:<name>`) and log file
+/// names (`<log_dir>/<name>.log`).
+fn window_name<'de, D: serde::Deserializer<'de>>(d: D) -> Result<String, D::Error> {
+    let name = String::deserialize(d)?;
+    if name.is_empty() || name.contains([':', '.', '/', '\\']) {
+        return Err(serde::de::Error::custom(format!(
+            "invalid window name '{name}': it can't be empty or contain `:`, `.`, `/` or `\\`"
+        )));
+    }
+    Ok(name)
+}
+
+pub const PROJECT_FILE: &str = ".projctl.toml";
+
 pub fn default_config_path() -> PathBuf {
-    expand_tilde("~/.config/projctl/config.toml")
+    config_dir().join("config.toml")
 }
 
 pub fn load_config(path: &Path) -> Result<FileConfig> {
@@ -50,3 +310,602 @@ pub fn load_config(path: &Path) -> Result<FileConfig> {
         toml::from_str(&text).with_context(|| format!("parsing {}", path.display()))?;
     Ok(cfg)
 }
+
+/// `.projctl.toml` in `projdir`, or defaults when there is none.
+pub fn load_project_file(projdir: &Path) -> Result<ProjectFile> {
+    let path = projdir.join(PROJECT_FILE);
+    if !path.exists() {
+        return Ok(ProjectFile::default());
+    }
+    let text = fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    toml::from_str(&text).with_context(|| format!("parsing {}", path.display()))
+}
+
+/// Which of projctl's config files a text belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigKind {
+    User,
+    Project,
+}
+
+/// Expected layout of a config file, for reporting unknown keys. Value types
+/// are left to serde.
+enum Shape {
+    Value,
+    Table(&'static [(&'static str, Shape)]),
+    /// Array of tables, e.g. `[[scan_roots]]`.
+    Tables(&'static [(&'static str, Shape)]),
+    /// Free-form keys, e.g. `[env]`.
+    Map,
+    /// Free-form keys, each holding a table, e.g. `[profiles.<name>]`.
+    Named(&'static [(&'static str, Shape)]),
+}
+
+const USER_KEYS: &[(&str, Shape)] = &[
+    ("profiles", Shape::Named(PROFILE_KEYS)),
+    ("editor", Shape::Value),
+    ("git_ui", Shape::Value),
+    ("file_manager", Shape::Value),
+    ("browser", Shape::Value),
+    ("projects_dir", Shape::Value),
+    ("log_dir", Shape::Value),
+    (
+        "scan_roots",
+        Shape::Tables(&[
+            ("path", Shape::Value),
+            ("depth", Shape::Value),
+            ("ignore", Shape::Value),
+        ]),
+    ),
+];
+
+/// A `[profiles.<name>]` table: the user keys, minus `profiles`.
+const PROFILE_KEYS: &[(&str, Shape)] = &[
+    ("editor", Shape::Value),
+    ("git_ui", Shape::Value),
+    ("file_manager", Shape::Value),
+    ("browser", Shape::Value),
+    ("projects_dir", Shape::Value),
+    ("log_dir", Shape::Value),
+    (
+        "scan_roots",
+        Shape::Tables(&[
+            ("path", Shape::Value),
+            ("depth", Shape::Value),
+            ("ignore", Shape::Value),
+        ]),
+    ),
+];
+
+const PROJECT_KEYS: &[(&str, Shape)] = &[
+    ("editor", Shape::Value),
+    ("git_ui", Shape::Value),
+    ("file_manager", Shape::Value),
+    ("browser", Shape::Value),
+    ("log_dir", Shape::Value),
+    ("database", Shape::Value),
+    ("env_files", Shape::Value),
+    ("env", Shape::Map),
+    (
+        "dev",
+        Shape::Table(&[("frontend", Shape::Value), ("backend", Shape::Value)]),
+    ),
+    (
+        "windows",
+        Shape::Tables(&[
+            ("name", Shape::Value),
+            ("dir", Shape::Value),
+            ("cmd", Shape::Value),
+        ]),
+    ),
+];
+
+/// A problem found in a config file.
+pub struct ConfigIssue {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Keys in `text` that projctl doesn't know about. Syntax errors are left
+/// to the regular parse.
+pub fn unknown_keys(text: &str, kind: ConfigKind) -> Vec<ConfigIssue> {
+    let Ok(doc) = toml::de::DeTable::parse(text) else {
+        return Vec::new();
+    };
+    let keys = match kind {
+        ConfigKind::User => USER_KEYS,
+        ConfigKind::Project => PROJECT_KEYS,
+    };
+    let mut issues = Vec::new();
+    check_table(text, doc.get_ref(), keys, "", &mut issues);
+    issues.sort_by_key(|i| i.line);
+    issues
+}
+
+fn check_table(
+    text: &str,
+    table: &toml::de::DeTable,
+    keys: &[(&str, Shape)],
+    prefix: &str,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    for (key, value) in table {
+        let name = key.get_ref().as_ref();
+        let path = format!("{prefix}{name}");
+        let Some((_, shape)) = keys.iter().find(|(k, _)| *k == name) else {
+            let hint = closest_key(name, keys.iter().map(|(k, _)| *k));
+            issues.push(ConfigIssue {
+                line: line_of(text, key.span().start),
+                message: match hint {
+                    Some(h) => format!("unknown key `{path}` (did you mean `{prefix}{h}`?)"),
+                    None => format!("unknown key `{path}`"),
+                },
+            });
+            continue;
+        };
+        match (shape, value.get_ref()) {
+            (Shape::Table(sub), toml::de::DeValue::Table(t)) => {
+                check_table(text, t, sub, &format!("{path}."), issues)
+            }
+            (Shape::Named(sub), toml::de::DeValue::Table(t)) => {
+                for (name, item) in t {
+                    if let toml::de::DeValue::Table(t) = item.get_ref() {
+                        let prefix = format!("{path}.{}.", name.get_ref());
+                        check_table(text, t, sub, &prefix, issues);
+                    }
+                }
+            }
+            (Shape::Tables(sub), toml::de::DeValue::Array(items)) => {
+                for (i, item) in items.iter().enumerate() {
+                    if let toml::de::DeValue::Table(t) = item.get_ref() {
+                        check_table(text, t, sub, &format!("{path}[{i}]."), issues);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn closest_key<'a>(key: &str, known: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    known
+        .map(|k| (strsim::levenshtein(key, k), k))
+        .filter(|(d, _)| *d <= 2)
+        .min()
+        .map(|(_, k)| k)
+}
+
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+/// Every problem in a config file: syntax and type errors from the parse,
+/// then unknown keys.
+pub fn validate_text(text: &str, kind: ConfigKind) -> Vec<ConfigIssue> {
+    let parsed = match kind {
+        ConfigKind::User => toml::from_str::<FileConfig>(text).err(),
+        ConfigKind::Project => toml::from_str::<ProjectFile>(text).err(),
+    };
+    if let Some(e) = parsed {
+        return vec![ConfigIssue {
+            line: e.span().map_or(0, |s| line_of(text, s.start)),
+            message: e.message().trim().to_string(),
+        }];
+    }
+    unknown_keys(text, kind)
+}
+
+/// Print a warning for each unknown key in the config file at `path`, if
+/// there is one. Loading ignores them, so a typo doesn't stop projctl.
+pub fn warn_unknown_keys(path: &Path, kind: ConfigKind) {
+    let Ok(text) = fs::read_to_string(path) else {
+        return;
+    };
+    for issue in unknown_keys(&text, kind) {
+        eprintln!(
+            "warning: {}:{}: {}",
+            path.display(),
+            issue.line,
+            issue.message
+        );
+    }
+}
+
+/// Keys `projctl config set` can write, per file.
+pub const SETTABLE_USER_KEYS: &[&str] = &[
+    "editor",
+    "git_ui",
+    "file_manager",
+    "browser",
+    "projects_dir",
+    "log_dir",
+];
+pub const SETTABLE_PROJECT_KEYS: &[&str] = &[
+    "editor",
+    "git_ui",
+    "file_manager",
+    "browser",
+    "log_dir",
+    "database",
+    "dev.frontend",
+    "dev.backend",
+];
+
+/// Set `key` (optionally `section.key`) to the string `value` in TOML
+/// `text`, editing lines in place so comments and layout survive.
+pub fn set_key(text: &str, key: &str, value: &str) -> String {
+    let (section, key) = match key.rsplit_once('.') {
+        Some((s, k)) => (Some(s), k),
+        None => (None, key),
+    };
+    let assignment = format!("{key} = {}", toml::Value::String(value.to_string()));
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+
+    // Line range [start, end) of the target section's body
+    let header = |l: &str| l.trim_start().starts_with('[');
+    let (start, end) = match section {
+        None => (
+            0,
+            lines.iter().position(|l| header(l)).unwrap_or(lines.len()),
+        ),
+        Some(s) => {
+            let Some(h) = lines
+                .iter()
+                .position(|l| table_header(l).is_some_and(|name| name == s))
+            else {
+                if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                    lines.push(String::new());
+                }
+                lines.push(format!("[{s}]"));
+                lines.push(assignment);
+                return lines.join("\n") + "\n";
+            };
+            let end = lines[h + 1..]
+                .iter()
+                .position(|l| header(l))
+                .map_or(lines.len(), |i| h + 1 + i);
+            (h + 1, end)
+        }
+    };
+
+    let is_key = |l: &str| {
+        l.split_once('=')
+            .is_some_and(|(k, _)| k.trim() == key && !l.trim_start().starts_with('#'))
+    };
+    if let Some(i) = lines[start..end].iter().position(|l| is_key(l)) {
+        lines[start + i] = assignment;
+    } else {
+        // After the last non-blank line of the section
+        let at = (start..end)
+            .rev()
+            .find(|&i| !lines[i].trim().is_empty())
+            .map_or(start, |i| i + 1);
+        // Keep a blank line before the next section header
+        if section.is_none() && at == start && start < lines.len() {
+            lines.insert(at, String::new());
+        }
+        lines.insert(at, assignment);
+    }
+    lines.join("\n") + "\n"
+}
+
+/// The dotted name of a `[table]` header line, ignoring whitespace, quotes
+/// around key parts and a trailing comment. `None` for other lines,
+/// including `[[array]]` headers.
+fn table_header(line: &str) -> Option<String> {
+    let line = line.split_once('#').map_or(line, |(l, _)| l).trim();
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    if inner.starts_with('[') {
+        return None;
+    }
+    let parts: Vec<&str> = inner
+        .split('.')
+        .map(|p| p.trim().trim_matches(['"', '\'']))
+        .collect();
+    Some(parts.join("."))
+}
+
+/// The layers a `ResolvedConfig` was merged from, for `projctl config show`.
+pub struct ConfigSources<'a> {
+    pub cli_editor: Option<&'a str>,
+    pub cli_git_ui: Option<&'a str>,
+    pub user_path: &'a Path,
+    /// The user config as written, before applying `profile`.
+    pub user: &'a FileConfig,
+    pub profile: Option<&'a str>,
+    pub project: Option<(PathBuf, &'a ProjectFile)>,
+}
+
+/// One effective setting and where it came from.
+pub struct Setting {
+    pub key: &'static str,
+    pub value: String,
+    pub source: String,
+}
+
+impl ResolvedConfig {
+    pub fn explain(&self, src: &ConfigSources) -> Vec<Setting> {
+        let user = src.user_path.display().to_string();
+        let project_path = src
+            .project
+            .as_ref()
+            .map(|(dir, _)| dir.join(PROJECT_FILE).display().to_string())
+            .unwrap_or_default();
+        let project = src.project.as_ref().map(|(_, p)| *p);
+        let profile = src.profile.and_then(|name| src.user.profiles.get(name));
+        let pick = |cli: Option<&str>, flag: &str, proj: bool, usr: fn(&FileConfig) -> bool| {
+            if cli.is_some() {
+                flag.to_string()
+            } else if proj {
+                project_path.clone()
+            } else if profile.is_some_and(usr) {
+                format!("{user} [profiles.{}]", src.profile.unwrap_or_default())
+            } else if usr(src.user) {
+                user.clone()
+            } else {
+                "default".to_string()
+            }
+        };
+        let setting = |key, value: String, source: String| Setting { key, value, source };
+        // Name the chain a tool was picked from
+        let tool = |key, tool: &Tool, source: String| {
+            let source = match tool.candidates.as_slice() {
+                [_] => source,
+                all => format!("{source}, first found of {}", all.join(", ")),
+            };
+            setting(key, tool.cmd().to_string(), source)
+        };
+        vec![
+            tool(
+                "editor",
+                &self.editor,
+                pick(
+                    src.cli_editor,
+                    "--editor",
+                    project.is_some_and(|p| p.editor.is_some()),
+                    |f| f.editor.is_some(),
+                ),
+            ),
+            tool(
+                "git_ui",
+                &self.git_ui,
+                pick(
+                    src.cli_git_ui,
+                    "--git-ui",
+                    project.is_some_and(|p| p.git_ui.is_some()),
+                    |f| f.git_ui.is_some(),
+                ),
+            ),
+            tool(
+                "file_manager",
+                &self.file_manager,
+                pick(
+                    None,
+                    "",
+                    project.is_some_and(|p| p.file_manager.is_some()),
+                    |f| f.file_manager.is_some(),
+                ),
+            ),
+            tool(
+                "browser",
+                &self.browser,
+                pick(
+                    None,
+                    "",
+                    project.is_some_and(|p| p.browser.is_some()),
+                    |f| f.browser.is_some(),
+                ),
+            ),
+            setting(
+                "projects_dir",
+                self.projects_dir.display().to_string(),
+                pick(None, "", false, |f| f.projects_dir.is_some()),
+            ),
+            setting(
+                "scan_roots",
+                self.scan_roots
+                    .iter()
+                    .skip(1)
+                    .map(|r| format!("{} (depth {})", r.path, r.depth))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                pick(None, "", false, |f| !f.scan_roots.is_empty()),
+            ),
+            setting(
+                "log_dir",
+                self.log_dir
+                    .clone()
+                    .unwrap_or_else(|| "logs (per app)".into()),
+                pick(
+                    None,
+                    "",
+                    project.is_some_and(|p| p.log_dir.is_some()),
+                    |f| f.log_dir.is_some(),
+                ),
+            ),
+            setting(
+                "dev.frontend",
+                self.dev
+                    .frontend
+                    .clone()
+                    .unwrap_or_else(|| "detected".into()),
+                pick(None, "", self.dev.frontend.is_some(), |_| false),
+            ),
+            setting(
+                "dev.backend",
+                self.dev
+                    .backend
+                    .clone()
+                    .unwrap_or_else(|| "detected".into()),
+                pick(None, "", self.dev.backend.is_some(), |_| false),
+            ),
+            setting(
+                "windows",
+                self.windows
+                    .iter()
+                    .map(|w| w.name.clone())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                pick(None, "", !self.windows.is_empty(), |_| false),
+            ),
+            setting(
+                "database",
+                self.database.clone().unwrap_or_default(),
+                pick(None, "", self.database.is_some(), |_| false),
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_key_replaces_in_place() {
+        let text = "# mine\neditor = \"vim\" # old\nlog_dir = \"logs\"\n";
+        assert_eq!(
+            set_key(text, "editor", "code -g {file}"),
+            "# mine\neditor = \"code -g {file}\"\nlog_dir = \"logs\"\n"
+        );
+    }
+
+    #[test]
+    fn set_key_adds_top_level_keys_above_sections() {
+        let text = "editor = \"vim\"\n\n[dev]\nfrontend = \"x\"\n";
+        assert_eq!(
+            set_key(text, "log_dir", "var/log"),
+            "editor = \"vim\"\nlog_dir = \"var/log\"\n\n[dev]\nfrontend = \"x\"\n"
+        );
+    }
+
+    #[test]
+    fn set_key_writes_into_sections() {
+        let text = "[dev]\nfrontend = \"x\"\n";
+        assert_eq!(
+            set_key(text, "dev.backend", "cargo run"),
+            "[dev]\nfrontend = \"x\"\nbackend = \"cargo run\"\n"
+        );
+        assert_eq!(
+            set_key("editor = \"vim\"\n", "dev.backend", "cargo run"),
+            "editor = \"vim\"\n\n[dev]\nbackend = \"cargo run\"\n"
+        );
+    }
+
+    #[test]
+    fn set_key_finds_headers_with_comments_and_spaces() {
+        for header in ["[dev] # servers", "[ dev ]", "[\"dev\"]"] {
+            let text = format!("{header}\nfrontend = \"x\"\n");
+            assert_eq!(
+                set_key(&text, "dev.backend", "y"),
+                format!("{header}\nfrontend = \"x\"\nbackend = \"y\"\n")
+            );
+        }
+        let text = "[profiles . work]\neditor = \"vim\"\n";
+        assert_eq!(
+            set_key(text, "profiles.work.editor", "code"),
+            "[profiles . work]\neditor = \"code\"\n"
+        );
+    }
+
+    #[test]
+    fn set_key_quotes_values() {
+        let out = set_key("", "editor", "say \"hi\"\\");
+        let parsed: FileConfig = toml::from_str(&out).unwrap();
+        assert!(matches!(parsed.editor, Some(Candidates::One(c)) if c == "say \"hi\"\\"));
+    }
+
+    #[test]
+    fn reports_unknown_keys_with_lines_and_hints() {
+        let text = "editr = \"vim\"\n[[scan_roots]]\npath = \"~/x\"\ndepht = 2\n[profiles.work]\nbogus = 1\n";
+        let issues = unknown_keys(text, ConfigKind::User);
+        let found: Vec<_> = issues
+            .iter()
+            .map(|i| (i.line, i.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (1, "unknown key `editr` (did you mean `editor`?)"),
+                (
+                    4,
+                    "unknown key `scan_roots[0].depht` (did you mean `scan_roots[0].depth`?)"
+                ),
+                (6, "unknown key `profiles.work.bogus`"),
+            ]
+        );
+        assert!(unknown_keys("database = \"x\"\n", ConfigKind::Project).is_empty());
+        assert_eq!(
+            unknown_keys("database = \"x\"\n", ConfigKind::User).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn rejects_window_names_that_break_targets_or_paths() {
+        let parse = |name: &str| {
+            toml::from_str::<ProjectFile>(&format!("[[windows]]\nname = \"{name}\"\n"))
+        };
+        assert!(parse("worker").is_ok());
+        for name in ["", "a:b", "a.b", "../x", "a\\\\b"] {
+            assert!(parse(name).is_err(), "{name}");
+        }
+        let issues = validate_text("[[windows]]\nname = \"a:b\"\n", ConfigKind::Project);
+        assert_eq!(issues[0].line, 2);
+        assert!(issues[0].message.contains("invalid window name"));
+    }
+
+    fn user_config() -> FileConfig {
+        toml::from_str(
+            r#"
+editor = "vim"
+git_ui = "tig"
+[[scan_roots]]
+path = "~/base"
+
+[profiles.work]
+editor = "code"
+[[profiles.work.scan_roots]]
+path = "~/work"
+
+[profiles.side]
+"#,
+        )
+        .unwrap()
+    }
+
+    fn one(c: &Option<Candidates>) -> &str {
+        match c {
+            Some(Candidates::One(c)) => c,
+            _ => panic!("expected a single command"),
+        }
+    }
+
+    #[test]
+    fn profile_settings_override_the_base() {
+        let work = user_config().for_profile(Some("work")).unwrap();
+        assert_eq!(one(&work.editor), "code");
+        assert_eq!(one(&work.git_ui), "tig");
+        let roots: Vec<_> = work.scan_roots.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(roots, ["~/work"]);
+
+        let side = user_config().for_profile(Some("side")).unwrap();
+        assert_eq!(one(&side.editor), "vim");
+        assert_eq!(side.scan_roots[0].path, "~/base");
+
+        let base = user_config().for_profile(None).unwrap();
+        assert_eq!(one(&base.editor), "vim");
+    }
+
+    #[test]
+    fn rejects_unknown_or_unsafe_profiles() {
+        let err = user_config().for_profile(Some("home")).unwrap_err();
+        assert!(
+            err.to_string().contains("Known profiles: side, work"),
+            "{err}"
+        );
+        for name in ["", ".hidden", "a/b", "..\\x"] {
+            assert!(user_config().for_profile(Some(name)).is_err(), "{name}");
+        }
+    }
+}
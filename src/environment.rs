@@ -0,0 +1,159 @@
+use crate::config::load_project_file;
+use crate::store::ProjectStore;
+use crate::utils::same_path;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Environment for commands run in the project at `projdir`. Later sources
+/// win: `.env` files (registry, then `.projctl.toml`), then `.projctl.toml`'s
+/// `[env]`, then the registry entry's own `env`.
+pub fn project_env(store: &dyn ProjectStore, projdir: &Path) -> Result<BTreeMap<String, String>> {
+    let projects = store.load()?;
+    let entry = projects
+        .projects
+        .values()
+        .find(|e| same_path(Path::new(&e.path), projdir));
+    let file = load_project_file(projdir)?;
+
+    let mut vars = BTreeMap::new();
+    let env_files = entry
+        .map(|e| e.env_files.iter())
+        .into_iter()
+        .flatten()
+        .chain(file.env_files.iter());
+    for f in env_files {
+        let path = projdir.join(f);
+        if !path.exists() {
+            eprintln!("warning: env file {} not found", path.display());
+            continue;
+        }
+        vars.extend(read_dotenv(&path)?);
+    }
+    // Names end up in eval'd `export` lines; .env files are checked on parse
+    let named = |(k, v): (String, String)| {
+        if is_var_name(&k) {
+            return Some((k, v));
+        }
+        eprintln!("warning: ignoring invalid variable name '{}'", k);
+        None
+    };
+    vars.extend(file.env.into_iter().filter_map(named));
+    if let Some(e) = entry {
+        vars.extend(e.env.clone().into_iter().filter_map(named));
+    }
+    Ok(vars)
+}
+
+/// Whether `key` is a valid environment variable name
+/// (`[A-Za-z_][A-Za-z0-9_]*`).
+pub fn is_var_name(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+pub fn read_dotenv(path: &Path) -> Result<Vec<(String, String)>> {
+    let text = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    parse_dotenv(&text).with_context(|| format!("parsing {}", path.display()))
+}
+
+/// Parse `.env` text: `KEY=value` lines with optional `export ` prefix, `#`
+/// comments, 'single-quoted' (literal) and "double-quoted" (with `\n`, `\"`,
+/// `\\` escapes) values.
+pub fn parse_dotenv(text: &str) -> Result<Vec<(String, String)>> {
+    let mut vars = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            anyhow::bail!("line {}: expected KEY=value", i + 1);
+        };
+        let key = key.trim();
+        if !is_var_name(key) {
+            anyhow::bail!("line {}: invalid variable name '{}'", i + 1, key);
+        }
+        let value = parse_value(value.trim()).with_context(|| format!("line {}", i + 1))?;
+        vars.push((key.to_string(), value));
+    }
+    Ok(vars)
+}
+
+fn parse_value(raw: &str) -> Result<String> {
+    if let Some(rest) = raw.strip_prefix('\'') {
+        let Some(end) = rest.find('\'') else {
+            anyhow::bail!("unterminated single quote");
+        };
+        return Ok(rest[..end].to_string());
+    }
+    if let Some(rest) = raw.strip_prefix('"') {
+        let mut out = String::new();
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Ok(out),
+                '\\' => match chars.next() {
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some(c) => out.push(c),
+                    None => break,
+                },
+                c => out.push(c),
+            }
+        }
+        anyhow::bail!("unterminated double quote");
+    }
+    // Unquoted: a ` #` starts a trailing comment
+    let value = match raw.find(" #") {
+        Some(i) => &raw[..i],
+        None => raw,
+    };
+    Ok(value.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dotenv() {
+        let text = r#"
+# comment
+export A=1
+B = plain value # trailing
+C='single #$x'
+D="line\nnext \"q\""
+E=
+"#;
+        let vars = parse_dotenv(text).unwrap();
+        let get = |k: &str| vars.iter().find(|(n, _)| n == k).map(|(_, v)| v.as_str());
+        assert_eq!(get("A"), Some("1"));
+        assert_eq!(get("B"), Some("plain value"));
+        assert_eq!(get("C"), Some("single #$x"));
+        assert_eq!(get("D"), Some("line\nnext \"q\""));
+        assert_eq!(get("E"), Some(""));
+    }
+
+    #[test]
+    fn rejects_bad_dotenv_lines() {
+        assert!(parse_dotenv("NOEQUALS").is_err());
+        assert!(parse_dotenv("1X=1").is_err());
+        assert!(parse_dotenv("X; echo pwned; Y=2").is_err());
+        assert!(parse_dotenv("A='open").is_err());
+        assert!(parse_dotenv("A=\"open").is_err());
+    }
+
+    #[test]
+    fn checks_var_names() {
+        assert!(is_var_name("_A1"));
+        assert!(!is_var_name(""));
+        assert!(!is_var_name("1A"));
+        assert!(!is_var_name("A-B"));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::models::{ProjectEntry, Projects};
+use crate::utils::expand_tilde;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Version of the export file format (independent of the registry schema).
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Portable snapshot of the registry, as written by `projctl export`.
+#[derive(Serialize, Deserialize)]
+pub struct Export {
+    pub version: u32,
+    #[serde(default)]
+    pub projects: BTreeMap<String, ExportedProject>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExportedProject {
+    /// Relative to the projects dir when the project lives inside it,
+    /// `~/`-relative when under the home dir, absolute otherwise.
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub links: BTreeMap<String, String>,
+}
+
+impl Export {
+    pub fn from_projects(projects: &Projects, projects_dir: &Path) -> Self {
+        let home = expand_tilde("~");
+        let projects = projects
+            .projects
+            .iter()
+            .map(|(name, e)| {
+                let path = Path::new(&e.path);
+                let path = if let Ok(rel) = path.strip_prefix(projects_dir) {
+                    rel.display().to_string()
+                } else if let Ok(rel) = path.strip_prefix(&home) {
+                    format!("~/{}", rel.display())
+                } else {
+                    e.path.clone()
+                };
+                let exported = ExportedProject {
+                    path,
+                    description: e.description.clone(),
+                    remote: e.remote.clone(),
+                    tags: e.tags.clone(),
+                    aliases: e.aliases.clone(),
+                    metadata: e.metadata.clone(),
+                    links: e.links.clone(),
+                };
+                (name.clone(), exported)
+            })
+            .collect();
+        Self {
+            version: EXPORT_VERSION,
+            projects,
+        }
+    }
+}
+
+impl ExportedProject {
+    /// Rewrite the exported path against the importing user's projects dir.
+    pub fn local_path(&self, projects_dir: &Path) -> PathBuf {
+        if self.path.starts_with('~') {
+            expand_tilde(&self.path)
+        } else {
+            let p = Path::new(&self.path);
+            if p.is_absolute() {
+                p.to_path_buf()
+            } else {
+                projects_dir.join(p)
+            }
+        }
+    }
+
+    pub fn into_entry(self, projects_dir: &Path) -> ProjectEntry {
+        let mut entry = ProjectEntry::new(self.local_path(projects_dir).display().to_string());
+        entry.description = self.description;
+        entry.remote = self.remote;
+        entry.tags = self.tags;
+        entry.aliases = self.aliases;
+        entry.metadata = self.metadata;
+        entry.links = self.links;
+        entry
+    }
+}
@@ -1,7 +1,12 @@
 pub mod cli;
 pub mod commands;
 pub mod config;
+pub mod environment;
+pub mod exchange;
 pub mod models;
+pub mod resolve;
 pub mod servers;
+pub mod shell;
+pub mod store;
 pub mod tmux;
 pub mod utils;
@@ -1,69 +1,273 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use clap::Parser;
-use projctl::cli::{Cli, Commands};
-use projctl::config::{ResolvedConfig, load_config};
-use projctl::models::ensure_projects_db;
-use projctl::utils::get_current_projdir;
+use projctl::cli::{Cli, Commands, ConfigCommands};
+use projctl::config::{
+    ConfigKind, ConfigSources, FileConfig, PROJECT_FILE, ProjectFile, ResolvedConfig, load_config,
+    load_project_file, warn_unknown_keys,
+};
+use projctl::store::{JsonFileStore, MemoryStore, ProjectStore};
+use projctl::utils::{
+    PROFILE_VAR, find_project, find_project_exact, get_current_projdir, get_projects_db,
+    set_profile,
+};
+use std::env;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    let file_cfg = load_config(&cli.config)?;
-    let cfg = ResolvedConfig::resolve(&cli, file_cfg);
+    // `config` and `profile` must still work (to validate or fix it) when
+    // the file is broken
+    let lenient = matches!(
+        cli.command,
+        Commands::Config { .. } | Commands::Profile { .. }
+    );
+    let user_file = match load_config(&cli.config) {
+        Err(e) if lenient => {
+            eprintln!("warning: {:#}", e);
+            FileConfig::default()
+        }
+        loaded => loaded?,
+    };
+    let profile = cli
+        .profile
+        .clone()
+        .or_else(|| env::var(PROFILE_VAR).ok())
+        .filter(|p| !p.is_empty());
+    // Read-only commands may fall back to the default profile; `config set`
+    // and `edit` must not write to the wrong one
+    let read_only = matches!(
+        cli.command,
+        Commands::Config {
+            action: ConfigCommands::Show | ConfigCommands::Validate
+        } | Commands::Profile { .. }
+    );
+    let (file_cfg, profile) = match user_file.clone().for_profile(profile.as_deref()) {
+        Ok(file_cfg) => (file_cfg, profile),
+        Err(e) if read_only => {
+            eprintln!("warning: {:#}", e);
+            (user_file.clone(), None)
+        }
+        Err(e) => return Err(e),
+    };
+    // Before anything opens the registry or state file
+    set_profile(profile);
+    let user_cfg = ResolvedConfig::resolve(&cli, file_cfg.clone(), ProjectFile::default());
+
+    // Prompt and shell hooks run constantly; keep them quiet and fast
+    let hook = matches!(
+        cli.command,
+        Commands::Prompt { .. }
+            | Commands::Chpwd { .. }
+            | Commands::Complete { .. }
+            | Commands::ShellInit { .. }
+    );
+
+    let json_store = JsonFileStore::new(get_projects_db());
+    // Hooks work on one unlocked read of the registry; a missing or broken
+    // one just looks empty to them
+    let snapshot;
+    let store: &dyn ProjectStore = match hook {
+        true => {
+            snapshot = MemoryStore::new(json_store.load_readonly().unwrap_or_default());
+            &snapshot
+        }
+        false => &json_store,
+    };
 
-    for (label, cmd) in [("editor", &cfg.editor), ("git_ui", &cfg.git_ui)] {
-        if cmd
-            .split_whitespace()
-            .next()
-            .is_some_and(|bin| which::which(bin).is_err())
-        {
-            eprintln!("warning: {label} command '{cmd}' not found in PATH");
+    // Resolve --project once so a fuzzy match doesn't prompt twice. Hooks
+    // can't answer a picker, so they only take exact names.
+    let project = match cli.project.as_deref() {
+        Some(name) => {
+            store.ensure()?;
+            let found = match hook {
+                true => find_project_exact(store, &user_cfg, name)?
+                    .ok_or_else(|| anyhow!("No project named '{}'", name))?,
+                false => find_project(store, &user_cfg, name)?,
+            };
+            Some(found.0)
+        }
+        None => None,
+    };
+    let project = project.as_deref();
+
+    // Layer the current project's `.projctl.toml` on top of the user config
+    let projdir = match hook {
+        true => None,
+        false => get_current_projdir(store, &user_cfg, project).ok(),
+    };
+    let project_file = projdir
+        .as_ref()
+        .and_then(|dir| match load_project_file(dir) {
+            Ok(project_file) => Some(project_file),
+            Err(e) => {
+                eprintln!("warning: ignoring {}: {:#}", PROJECT_FILE, e);
+                None
+            }
+        });
+    // `config validate` reports these itself
+    if !hook
+        && !matches!(
+            cli.command,
+            Commands::Config {
+                action: ConfigCommands::Validate
+            }
+        )
+    {
+        warn_unknown_keys(&cli.config, ConfigKind::User);
+        if let Some(dir) = &projdir {
+            warn_unknown_keys(&dir.join(PROJECT_FILE), ConfigKind::Project);
         }
     }
+    let cfg = match &project_file {
+        Some(project_file) => ResolvedConfig::resolve(&cli, file_cfg.clone(), project_file.clone()),
+        None => user_cfg,
+    };
 
     // Ensure db exists for relevant commands
     match &cli.command {
         Commands::Add { .. }
         | Commands::Use { .. }
-        | Commands::List
+        | Commands::Push { .. }
+        | Commands::Pop { .. }
+        | Commands::Stack
+        | Commands::List { .. }
         | Commands::Remove { .. }
+        | Commands::Rename { .. }
+        | Commands::Move { .. }
+        | Commands::Prune { .. }
+        | Commands::Relink { .. }
+        | Commands::Export { .. }
+        | Commands::Import { .. }
         | Commands::Path { .. }
+        | Commands::Which { .. }
+        | Commands::Cd { .. }
+        | Commands::Complete { .. }
+        | Commands::Tag { .. }
+        | Commands::Alias { .. }
+        | Commands::Archive { .. }
+        | Commands::Unarchive { .. }
+        | Commands::Pin { .. }
+        | Commands::Unpin { .. }
         | Commands::Run { .. }
-        | Commands::Edit
+        | Commands::Edit { .. }
+        | Commands::Open
         | Commands::Git
         | Commands::Logs { .. }
-        | Commands::Servers { .. } => ensure_projects_db()?,
+        | Commands::Note { .. }
+        | Commands::Link { .. }
+        | Commands::Browse { .. }
+        | Commands::Env { .. }
+        | Commands::Hook { .. }
+        | Commands::Servers { .. } => store.ensure()?,
         _ => {}
     }
 
     match cli.command {
-        Commands::Add(args) => projctl::commands::add_args(args),
-        Commands::Use { name } => projctl::commands::use_proj(name),
-        Commands::List => projctl::commands::list(),
-        Commands::Remove { name } => projctl::commands::remove(name),
-        Commands::Path { name } => projctl::commands::path_cmd(name),
+        Commands::Add(args) => projctl::commands::add_args(store, &cfg, args),
+        Commands::Use {
+            name,
+            tags,
+            archived,
+            emit,
+        } => projctl::commands::use_proj(store, &cfg, name, &tags, archived, emit),
+        Commands::Push { name, emit } => projctl::commands::push(store, name, emit),
+        Commands::Pop { emit } => projctl::commands::pop(store, emit),
+        Commands::Stack => projctl::commands::stack(store, &cfg),
+        Commands::List { tags, archived } => projctl::commands::list(store, &cfg, &tags, archived),
+        Commands::Remove { name } => projctl::commands::remove(store, name),
+        Commands::Rename { old, new } => projctl::commands::rename(store, old, new),
+        Commands::Move { name, path } => projctl::commands::move_proj(store, name, path),
+        Commands::Prune { dry_run } => projctl::commands::prune(store, dry_run),
+        Commands::Relink { name } => projctl::commands::relink(store, &cfg, name),
+        Commands::Export { format, output } => {
+            projctl::commands::export(store, &cfg, format, output)
+        }
+        Commands::Import { file, on_conflict } => {
+            projctl::commands::import(store, &cfg, file, on_conflict)
+        }
+        Commands::Path {
+            name,
+            tags,
+            archived,
+        } => projctl::commands::path_cmd(store, &cfg, project, name, &tags, archived),
+        Commands::Cd { name, subdir, emit } => {
+            projctl::commands::cd_proj(store, &cfg, project, name, subdir, emit)
+        }
+        Commands::Complete { what } => projctl::commands::complete(store, &cfg, what),
+        Commands::Which { path } => projctl::commands::which_proj(store, path),
+        Commands::Tag { action } => projctl::commands::tag(store, action),
+        Commands::Alias { action } => projctl::commands::alias(store, action),
+        Commands::Archive { name } => projctl::commands::archive(store, name, true),
+        Commands::Unarchive { name } => projctl::commands::archive(store, name, false),
+        Commands::Pin { name } => projctl::commands::pin(store, name, true),
+        Commands::Unpin { name } => projctl::commands::pin(store, name, false),
         Commands::Run { cmd } => {
-            let projdir = get_current_projdir()?;
-            projctl::commands::run(projdir, cmd)
+            let projdir = get_current_projdir(store, &cfg, project)?;
+            projctl::commands::run(store, projdir, cmd)
         }
-        Commands::Edit => {
-            let projdir = get_current_projdir()?;
-            projctl::commands::edit(projdir, &cfg)
+        Commands::Edit { target } => {
+            let projdir = get_current_projdir(store, &cfg, project)?;
+            projctl::commands::edit(store, projdir, &cfg, target)
+        }
+        Commands::Open => {
+            let projdir = get_current_projdir(store, &cfg, project)?;
+            projctl::commands::open(store, projdir, &cfg)
         }
         Commands::Git => {
-            let projdir = get_current_projdir()?;
-            projctl::commands::git(projdir, &cfg)
+            let projdir = get_current_projdir(store, &cfg, project)?;
+            projctl::commands::git(store, projdir, &cfg)
+        }
+        Commands::Logs { path } => projctl::commands::logs(store, &cfg, project, path),
+        Commands::Note { text } => {
+            let projdir = get_current_projdir(store, &cfg, project)?;
+            projctl::commands::note(store, projdir, text, &cfg)
+        }
+        Commands::Link { action } => {
+            let projdir = get_current_projdir(store, &cfg, project)?;
+            projctl::commands::link(store, projdir, action)
+        }
+        Commands::Browse { link } => {
+            let projdir = get_current_projdir(store, &cfg, project)?;
+            projctl::commands::browse(store, projdir, &cfg, link)
+        }
+        Commands::Hook { action } => {
+            let projdir = get_current_projdir(store, &cfg, project)?;
+            projctl::commands::hook(store, projdir, action)
+        }
+        Commands::Env { action, shell } => {
+            let projdir = get_current_projdir(store, &cfg, project)?;
+            projctl::commands::env_cmd(store, projdir, shell, action)
         }
-        Commands::Logs { path } => projctl::commands::logs(path),
         Commands::Servers {
             refresh,
             reset,
             kill,
         } => {
-            let proj_dir = get_current_projdir()?;
-            projctl::commands::servers(proj_dir, refresh, reset, kill)
+            let proj_dir = get_current_projdir(store, &cfg, project)?;
+            projctl::commands::servers(store, &cfg, proj_dir, refresh, reset, kill)
+        }
+        Commands::ShellInit {
+            shell,
+            no_auto_switch,
+        } => {
+            print!("{}", projctl::shell::init_script(shell, !no_auto_switch));
+            Ok(())
+        }
+        Commands::Prompt { format } => projctl::commands::prompt(store, &cfg, project, format),
+        Commands::Chpwd { shell } => projctl::commands::chpwd(store, shell),
+        Commands::Config { action } => {
+            let sources = ConfigSources {
+                cli_editor: cli.editor.as_deref(),
+                cli_git_ui: cli.git_ui.as_deref(),
+                user_path: &cli.config,
+                user: &user_file,
+                profile: projctl::utils::profile(),
+                project: projdir.clone().zip(project_file.as_ref()),
+            };
+            projctl::commands::config_cmd(&cfg, &sources, projdir, action)
         }
-        Commands::DbCreate { name } => projctl::commands::create_db(name),
+        Commands::Profile { action } => projctl::commands::profile_cmd(&user_file, action),
+        Commands::DbCreate { name } => projctl::commands::create_db(name, &cfg),
     }
 }
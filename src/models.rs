@@ -1,45 +1,164 @@
-use crate::utils::get_projects_db;
-use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::fs;
-use std::io::Read;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Serialize, Deserialize)]
+/// Current on-disk registry schema version.
+pub const REGISTRY_VERSION: u32 = 2;
+
+/// Number of recently used project names kept in `history`.
+const HISTORY_LEN: usize = 20;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Projects {
+    pub version: u32,
     pub current: Option<String>,
-    #[serde(flatten)]
-    pub projects: HashMap<String, String>,
+    /// Most recently used project names, newest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<String>,
+    /// Projects to return to with `projctl pop`, most recent last.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stack: Vec<String>,
+    #[serde(default)]
+    pub projects: HashMap<String, ProjectEntry>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectEntry {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Unix timestamp (seconds) of when the project was added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    /// Unix timestamp (seconds) of the last `use` of the project.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<u64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// Hidden from `list`, the `use` picker and fuzzy matching.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+    /// Named URLs (dashboards, repos, ...) opened with `projctl browse`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub links: BTreeMap<String, String>,
+    /// Extra environment for commands run in the project (`run`, editor, git
+    /// UI, tmux panes).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// `.env` files loaded before `env`, relative to the project dir.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env_files: Vec<String>,
+    /// Shell command run (in the project dir) when switching to the project.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_enter: Option<String>,
+    /// Shell command run (in the project dir) when switching away from it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_leave: Option<String>,
+    /// `origin` URL last seen for the project; lets `relink` find it again
+    /// after the folder moves.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
 }
 
-pub fn ensure_projects_db() -> Result<()> {
-    let db_path = get_projects_db();
-    if let Some(parent) = db_path.parent() {
-        fs::create_dir_all(parent)?;
+impl ProjectEntry {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            created_at: Some(now_unix()),
+            ..Default::default()
+        }
     }
-    if !db_path.exists() {
-        let projects = Projects {
-            current: None,
-            projects: HashMap::new(),
+
+    pub fn exists(&self) -> bool {
+        Path::new(&self.path).exists()
+    }
+
+    /// True if the entry carries every tag in `tags` (vacuously true when empty).
+    pub fn has_tags(&self, tags: &[String]) -> bool {
+        tags.iter().all(|t| self.tags.contains(t))
+    }
+}
+
+impl Projects {
+    /// Make `name` current and move it to the front of the history.
+    pub fn record_use(&mut self, name: &str) {
+        self.current = Some(name.to_string());
+        self.touch(name);
+    }
+
+    /// Bump `name` in the history and recency order without making it the
+    /// global current project (used for per-shell switches).
+    pub fn touch(&mut self, name: &str) {
+        if let Some(entry) = self.projects.get_mut(name) {
+            entry.last_used = Some(now_unix());
+        }
+        self.history.retain(|n| n != name);
+        self.history.insert(0, name.to_string());
+        self.history.truncate(HISTORY_LEN);
+    }
+
+    /// Drop `name` from the history and the push/pop stack (after removal).
+    pub fn forget(&mut self, name: &str) {
+        self.history.retain(|n| n != name);
+        self.stack.retain(|n| n != name);
+    }
+
+    /// The most recently used project other than the current one.
+    pub fn previous(&self) -> Option<&str> {
+        self.previous_of(self.current.as_deref())
+    }
+
+    /// Most recently used project other than `current`.
+    pub fn previous_of(&self, current: Option<&str>) -> Option<&str> {
+        self.history
+            .iter()
+            .find(|n| Some(n.as_str()) != current && self.projects.contains_key(*n))
+            .map(String::as_str)
+    }
+
+    /// Entries in picker order: pinned first, then most recently used, then
+    /// by name.
+    pub fn ordered(&self) -> Vec<(&String, &ProjectEntry)> {
+        let rank = |name: &String| {
+            self.history
+                .iter()
+                .position(|n| n == name)
+                .unwrap_or(usize::MAX)
         };
-        let json = serde_json::to_string_pretty(&projects)?;
-        fs::write(&db_path, json)?;
+        let mut entries: Vec<_> = self.projects.iter().collect();
+        entries.sort_by(|(an, a), (bn, b)| {
+            b.pinned
+                .cmp(&a.pinned)
+                .then(rank(an).cmp(&rank(bn)))
+                .then(b.last_used.cmp(&a.last_used))
+                .then(an.cmp(bn))
+        });
+        entries
     }
-    Ok(())
 }
 
-pub fn load_projects() -> Result<Projects> {
-    let db_path = get_projects_db();
-    let mut file = std::fs::File::open(&db_path)?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
-    let projects: Projects = serde_json::from_str(&contents)?;
-    Ok(projects)
+impl Default for Projects {
+    fn default() -> Self {
+        Self {
+            version: REGISTRY_VERSION,
+            current: None,
+            history: Vec::new(),
+            stack: Vec::new(),
+            projects: HashMap::new(),
+        }
+    }
 }
 
-pub fn save_projects(projects: &Projects) -> Result<()> {
-    let db_path = get_projects_db();
-    let json = serde_json::to_string_pretty(projects)?;
-    fs::write(&db_path, json)?;
-    Ok(())
+pub fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
 }
@@ -0,0 +1,263 @@
+use crate::models::Projects;
+use anyhow::{Result, anyhow};
+use dialoguer::{Select, theme::ColorfulTheme};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    Exact,
+    Alias,
+    Prefix,
+    Fuzzy,
+}
+
+pub struct Resolved {
+    pub name: String,
+    pub kind: MatchKind,
+}
+
+pub enum Resolution {
+    Found(Resolved),
+    NotFound { suggestions: Vec<String> },
+}
+
+/// Resolve a user-supplied project name against the registry, in order:
+/// exact name, alias, unique prefix (of a name or alias), fuzzy subsequence.
+/// Several prefix/fuzzy candidates bring up a picker limited to them.
+/// Archived projects only match exactly unless `include_archived` is set.
+pub fn resolve(projects: &Projects, query: &str, include_archived: bool) -> Result<Resolution> {
+    // Every name would match an empty prefix
+    if query.trim().is_empty() {
+        anyhow::bail!("Project name can't be empty");
+    }
+    if let Some(r) = resolve_exact(projects, query) {
+        return Ok(Resolution::Found(r));
+    }
+
+    let prefixed = candidates(projects, include_archived, |key| key.starts_with(query));
+    if let Some(name) = pick_candidate(projects, query, &prefixed)? {
+        return Ok(found(&name, MatchKind::Prefix));
+    }
+
+    let fuzzy = candidates(projects, include_archived, |key| is_subsequence(query, key));
+    if let Some(name) = pick_candidate(projects, query, &fuzzy)? {
+        return Ok(found(&name, MatchKind::Fuzzy));
+    }
+
+    Ok(Resolution::NotFound {
+        suggestions: suggestions(projects, query, include_archived),
+    })
+}
+
+/// Only the unambiguous steps of [`resolve`]: exact name, then alias.
+pub fn resolve_exact(projects: &Projects, query: &str) -> Option<Resolved> {
+    if projects.projects.contains_key(query) {
+        return Some(resolved(query, MatchKind::Exact));
+    }
+    projects
+        .projects
+        .iter()
+        .find(|(_, e)| e.aliases.iter().any(|a| a == query))
+        .map(|(name, _)| resolved(name, MatchKind::Alias))
+}
+
+/// Like [`resolve`] (skipping archived projects), but a miss becomes a
+/// "did you mean" error.
+pub fn resolve_project(projects: &Projects, query: &str) -> Result<Resolved> {
+    resolve_project_in(projects, query, false)
+}
+
+pub fn resolve_project_in(
+    projects: &Projects,
+    query: &str,
+    include_archived: bool,
+) -> Result<Resolved> {
+    match resolve(projects, query, include_archived)? {
+        Resolution::Found(r) => {
+            if r.kind == MatchKind::Fuzzy {
+                eprintln!("Using '{}' for '{}'", r.name, query);
+            }
+            Ok(r)
+        }
+        Resolution::NotFound { suggestions } => Err(not_found(query, &suggestions)),
+    }
+}
+
+pub fn not_found(query: &str, suggestions: &[String]) -> anyhow::Error {
+    if suggestions.is_empty() {
+        anyhow!(
+            "Project '{}' not found. Hint: run `projctl add` to add it.",
+            query
+        )
+    } else {
+        anyhow!(
+            "Project '{}' not found. Did you mean: {}?",
+            query,
+            suggestions.join(", ")
+        )
+    }
+}
+
+fn found(name: &str, kind: MatchKind) -> Resolution {
+    Resolution::Found(resolved(name, kind))
+}
+
+fn resolved(name: &str, kind: MatchKind) -> Resolved {
+    Resolved {
+        name: name.to_string(),
+        kind,
+    }
+}
+
+/// Project names whose own name or any alias satisfies `pred`, sorted.
+fn candidates(
+    projects: &Projects,
+    include_archived: bool,
+    pred: impl Fn(&str) -> bool,
+) -> Vec<String> {
+    let mut names: Vec<String> = projects
+        .projects
+        .iter()
+        .filter(|(_, e)| include_archived || !e.archived)
+        .filter(|(name, e)| pred(name) || e.aliases.iter().any(|a| pred(a)))
+        .map(|(name, _)| name.clone())
+        .collect();
+    names.sort();
+    names
+}
+
+fn pick_candidate(projects: &Projects, query: &str, names: &[String]) -> Result<Option<String>> {
+    match names {
+        [] => Ok(None),
+        [only] => Ok(Some(only.clone())),
+        _ => {
+            if !console::user_attended_stderr() {
+                anyhow::bail!("'{}' is ambiguous: {}", query, names.join(", "));
+            }
+            let labels: Vec<String> = names
+                .iter()
+                .map(|n| format!("{n}    {}", projects.projects[n].path))
+                .collect();
+            let idx = Select::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!("'{query}' matches several projects"))
+                .items(&labels)
+                .default(0)
+                .interact()?;
+            Ok(Some(names[idx].clone()))
+        }
+    }
+}
+
+/// Case-insensitive: every char of `needle` appears in `hay`, in order.
+fn is_subsequence(needle: &str, hay: &str) -> bool {
+    let mut hay = hay.chars().flat_map(char::to_lowercase);
+    needle
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|c| hay.any(|h| h == c))
+}
+
+/// Up to three names/aliases within a small edit distance of `query`.
+fn suggestions(projects: &Projects, query: &str, include_archived: bool) -> Vec<String> {
+    let max_dist = (query.chars().count() / 3).max(2);
+    let mut scored: Vec<(usize, String)> = projects
+        .projects
+        .iter()
+        .filter(|(_, e)| include_archived || !e.archived)
+        .flat_map(|(name, e)| std::iter::once(name).chain(e.aliases.iter()))
+        .map(|key| (strsim::levenshtein(query, key), key.clone()))
+        .filter(|(d, _)| *d <= max_dist)
+        .collect();
+    scored.sort();
+    scored.dedup_by(|a, b| a.1 == b.1);
+    scored.into_iter().take(3).map(|(_, k)| k).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ProjectEntry;
+    use crate::store::{MemoryStore, ProjectStore};
+
+    fn registry() -> Projects {
+        let mut projects = Projects::default();
+        for (name, aliases) in [
+            ("api", &["backend"][..]),
+            ("web", &["api-web"][..]),
+            ("infra", &[][..]),
+        ] {
+            let mut entry = ProjectEntry::new(format!("/src/{name}"));
+            entry.aliases = aliases.iter().map(|a| a.to_string()).collect();
+            projects.projects.insert(name.to_string(), entry);
+        }
+        let mut old = ProjectEntry::new("/src/legacy");
+        old.archived = true;
+        projects.projects.insert("legacy".into(), old);
+        projects
+    }
+
+    fn name_and_kind(projects: &Projects, query: &str) -> Option<(String, MatchKind)> {
+        match resolve(projects, query, false).unwrap() {
+            Resolution::Found(r) => Some((r.name, r.kind)),
+            Resolution::NotFound { .. } => None,
+        }
+    }
+
+    #[test]
+    fn resolves_in_order() {
+        let store = MemoryStore::new(registry());
+        let projects = (&store as &dyn ProjectStore).load().unwrap();
+        // An exact name beats the `api-web` alias prefix
+        assert_eq!(
+            name_and_kind(&projects, "api"),
+            Some(("api".into(), MatchKind::Exact))
+        );
+        assert_eq!(
+            name_and_kind(&projects, "backend"),
+            Some(("api".into(), MatchKind::Alias))
+        );
+        assert_eq!(
+            name_and_kind(&projects, "inf"),
+            Some(("infra".into(), MatchKind::Prefix))
+        );
+        assert_eq!(
+            name_and_kind(&projects, "ifa"),
+            Some(("infra".into(), MatchKind::Fuzzy))
+        );
+        assert_eq!(name_and_kind(&projects, "zzz"), None);
+    }
+
+    #[test]
+    fn archived_projects_match_only_exactly() {
+        let projects = registry();
+        assert_eq!(name_and_kind(&projects, "leg"), None);
+        assert_eq!(
+            name_and_kind(&projects, "legacy"),
+            Some(("legacy".into(), MatchKind::Exact))
+        );
+        assert!(matches!(
+            resolve(&projects, "leg", true).unwrap(),
+            Resolution::Found(r) if r.name == "legacy"
+        ));
+    }
+
+    #[test]
+    fn rejects_empty_queries() {
+        assert!(resolve(&registry(), "", true).is_err());
+        assert!(resolve(&registry(), "  ", false).is_err());
+    }
+
+    #[test]
+    fn suggests_close_names() {
+        let Err(err) = resolve_project(&registry(), "inrfa") else {
+            panic!("'inrfa' should not resolve");
+        };
+        assert!(err.to_string().contains("Did you mean: infra?"), "{err}");
+    }
+
+    #[test]
+    fn subsequence_ignores_case() {
+        assert!(is_subsequence("aB", "xAyb"));
+        assert!(is_subsequence("", "x"));
+        assert!(!is_subsequence("ba", "ab"));
+    }
+}
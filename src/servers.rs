@@ -1,19 +1,108 @@
+use crate::config::ResolvedConfig;
 use crate::tmux::{
     Tmux, attach_or_switch, ensure_server, ensure_windows, send_to_target_sh, setup_docker_layout,
 };
-use crate::utils::{compose_file, detect_dev_cmd, guess_backend_dir, guess_frontend_dir};
+use crate::utils::{
+    cache_dir, compose_file, detect_dev_cmd, guess_backend_dir, guess_frontend_dir, profile,
+    profile_dir,
+};
 use anyhow::Result;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 const TMUX_LABEL: &str = "projctl"; // isolate from user's default tmux
 
-pub fn setup_servers(proj_dir: &Path, refresh: bool, reset: bool, kill: bool) -> Result<()> {
-    let proj_name = proj_dir
-        .file_name()
-        .expect("project dir has a name")
-        .to_string_lossy()
-        .to_string();
-    let session = format!("{proj_name}-servers");
+/// `<name>-servers`, plus `@<profile>` outside the default profile so two
+/// profiles' projects of the same name get separate sessions.
+pub fn session_name(proj_name: &str) -> String {
+    match profile() {
+        Some(profile) => format!("{proj_name}-servers@{profile}"),
+        None => format!("{proj_name}-servers"),
+    }
+}
+
+/// Whether `<name>-servers` is running on the projctl tmux server. A missing
+/// tmux binary or server counts as "not running".
+pub fn session_running(proj_name: &str) -> bool {
+    Tmux::new(TMUX_LABEL)
+        .ok(["has-session", "-t", &session_name(proj_name)])
+        .unwrap_or(false)
+}
+
+/// Marker left in the cache dir while projctl's session for a project is
+/// running. It holds the tmux socket path, so `projctl prompt` can answer
+/// "running?" without spawning tmux.
+fn marker_path(proj_name: &str) -> PathBuf {
+    profile_dir(cache_dir(), profile())
+        .join("sessions")
+        .join(session_name(proj_name))
+}
+
+/// Best-effort: a stale or missing marker only affects the prompt segment.
+fn mark_running(tmux: &Tmux, proj_name: &str) {
+    let Ok(socket) = tmux.out([
+        "display-message",
+        "-p",
+        "-t",
+        &session_name(proj_name),
+        "#{socket_path}",
+    ]) else {
+        return;
+    };
+    let path = marker_path(proj_name);
+    if let Some(parent) = path.parent()
+        && fs::create_dir_all(parent).is_ok()
+    {
+        let _ = fs::write(path, socket.trim());
+    }
+}
+
+fn clear_marker(proj_name: &str) {
+    let _ = fs::remove_file(marker_path(proj_name));
+}
+
+/// Cheap variant of [`session_running`] for prompts: the session was started
+/// by projctl, has not been killed through it, and its tmux server's socket
+/// still exists. Sessions killed behind projctl's back may still show up.
+pub fn session_marked_running(proj_name: &str) -> bool {
+    fs::read_to_string(marker_path(proj_name)).is_ok_and(|socket| Path::new(socket.trim()).exists())
+}
+
+/// Rename `<old>-servers` to `<new>-servers` if it is running.
+pub fn rename_session(old: &str, new: &str) -> Result<bool> {
+    if !session_running(old) {
+        return Ok(false);
+    }
+    let tmux = Tmux::new(TMUX_LABEL);
+    tmux.run([
+        "rename-session",
+        "-t",
+        &session_name(old),
+        &session_name(new),
+    ])?;
+    clear_marker(old);
+    mark_running(&tmux, new);
+    Ok(true)
+}
+
+pub fn kill_session(proj_name: &str) -> Result<()> {
+    Tmux::new(TMUX_LABEL).run(["kill-session", "-t", &session_name(proj_name)])?;
+    clear_marker(proj_name);
+    Ok(())
+}
+
+pub fn setup_servers(
+    proj_name: &str,
+    proj_dir: &Path,
+    cfg: &ResolvedConfig,
+    env: &BTreeMap<String, String>,
+    refresh: bool,
+    reset: bool,
+    kill: bool,
+) -> Result<()> {
+    let session = session_name(proj_name);
 
     let tmux = Tmux::new(TMUX_LABEL);
 
@@ -28,12 +117,14 @@ pub fn setup_servers(proj_dir: &Path, refresh: bool, reset: bool, kill: bool) ->
         } else {
             println!("No session '{session}' to kill.");
         }
+        clear_marker(proj_name);
         return Ok(());
     }
 
     if reset && has_session {
         println!("Resetting session '{session}'...");
         tmux.run(["kill-session", "-t", &session])?;
+        clear_marker(proj_name);
         has_session = false;
     }
 
@@ -49,6 +140,7 @@ pub fn setup_servers(proj_dir: &Path, refresh: bool, reset: bool, kill: bool) ->
             "-c",
             proj_dir.to_str().unwrap(),
         ])?;
+        mark_running(&tmux, proj_name);
 
         ensure_windows(&tmux, &session, "backend", proj_dir)?;
         ensure_windows(&tmux, &session, "docker", proj_dir)?;
@@ -56,9 +148,10 @@ pub fn setup_servers(proj_dir: &Path, refresh: bool, reset: bool, kill: bool) ->
         ensure_windows(&tmux, &session, "scratch", proj_dir)?;
 
         let panes = setup_docker_layout(&tmux, &session)?;
-        seed_frontend(&tmux, &session, proj_dir)?;
-        seed_backend(&tmux, &session, proj_dir)?;
-        seed_docker(&tmux, proj_dir, &panes)?;
+        seed_frontend(&tmux, &session, proj_dir, cfg, env)?;
+        seed_backend(&tmux, &session, proj_dir, cfg, env)?;
+        seed_docker(&tmux, proj_dir, env, &panes)?;
+        seed_extra_windows(&tmux, &session, proj_dir, cfg, env)?;
 
         attach_or_switch(&tmux, &session)?;
         return Ok(());
@@ -74,14 +167,16 @@ pub fn setup_servers(proj_dir: &Path, refresh: bool, reset: bool, kill: bool) ->
         ensure_windows(&tmux, &session, "scratch", proj_dir)?;
 
         let panes = setup_docker_layout(&tmux, &session)?;
-        seed_frontend(&tmux, &session, proj_dir)?;
-        seed_backend(&tmux, &session, proj_dir)?;
-        seed_docker(&tmux, proj_dir, &panes)?;
+        seed_frontend(&tmux, &session, proj_dir, cfg, env)?;
+        seed_backend(&tmux, &session, proj_dir, cfg, env)?;
+        seed_docker(&tmux, proj_dir, env, &panes)?;
+        seed_extra_windows(&tmux, &session, proj_dir, cfg, env)?;
 
         attach_or_switch(&tmux, &session)?;
         return Ok(());
     }
 
+    mark_running(&tmux, proj_name);
     println!("Session '{session}' exists - attaching.");
     attach_or_switch(&tmux, &session)?;
     Ok(())
@@ -89,47 +184,129 @@ pub fn setup_servers(proj_dir: &Path, refresh: bool, reset: bool, kill: bool) ->
 
 /* ------------------------- seeding -------------------- */
 
-fn seed_frontend(tmux: &Tmux, session: &str, proj_dir: &Path) -> Result<()> {
+fn seed_frontend(
+    tmux: &Tmux,
+    session: &str,
+    proj_dir: &Path,
+    cfg: &ResolvedConfig,
+    env: &BTreeMap<String, String>,
+) -> Result<()> {
     let front_dir = guess_frontend_dir(proj_dir).unwrap_or_else(|| proj_dir.to_path_buf());
-    let front_cmd = detect_dev_cmd(&front_dir);
-    send_to_target_sh(tmux, &format!("{session}:frontend"), &front_dir, &front_cmd)
+    let front_cmd = match cfg
+        .dev
+        .frontend
+        .clone()
+        .or_else(|| detect_dev_cmd(&front_dir))
+    {
+        Some(cmd) => logged(cfg, proj_dir, "frontend", &cmd),
+        None => no_dev_cmd(),
+    };
+    send_to_target_sh(
+        tmux,
+        &format!("{session}:frontend"),
+        &front_dir,
+        env,
+        &front_cmd,
+    )
 }
 
-fn seed_backend(tmux: &Tmux, session: &str, proj_dir: &Path) -> Result<()> {
-    if let Some(bd) = guess_backend_dir(proj_dir) {
-        let back_cmd = detect_dev_cmd(&bd);
-        send_to_target_sh(tmux, &format!("{session}:backend"), &bd, &back_cmd)
-    } else {
-        send_to_target_sh(
-            tmux,
-            &format! {"{session}:backend"},
-            proj_dir,
-            "echo 'No backend dir found'; exec $SHELL",
-        )
+fn seed_backend(
+    tmux: &Tmux,
+    session: &str,
+    proj_dir: &Path,
+    cfg: &ResolvedConfig,
+    env: &BTreeMap<String, String>,
+) -> Result<()> {
+    let backend_dir = guess_backend_dir(proj_dir);
+    let back_cmd = match &backend_dir {
+        Some(bd) => cfg.dev.backend.clone().or_else(|| detect_dev_cmd(bd)),
+        None => cfg.dev.backend.clone(),
+    };
+    let (dir, cmd) = match (backend_dir, back_cmd) {
+        (dir, Some(cmd)) => (
+            dir.unwrap_or_else(|| proj_dir.to_path_buf()),
+            logged(cfg, proj_dir, "backend", &cmd),
+        ),
+        (Some(dir), None) => (dir, no_dev_cmd()),
+        (None, None) => (
+            proj_dir.to_path_buf(),
+            "echo 'No backend dir found'; exec $SHELL".to_string(),
+        ),
+    };
+    send_to_target_sh(tmux, &format!("{session}:backend"), &dir, env, &cmd)
+}
+
+/// `[[windows]]` from `.projctl.toml`.
+fn seed_extra_windows(
+    tmux: &Tmux,
+    session: &str,
+    proj_dir: &Path,
+    cfg: &ResolvedConfig,
+    env: &BTreeMap<String, String>,
+) -> Result<()> {
+    for w in &cfg.windows {
+        let dir = match &w.dir {
+            Some(d) => proj_dir.join(d),
+            None => proj_dir.to_path_buf(),
+        };
+        ensure_windows(tmux, session, &w.name, &dir)?;
+        if let Some(cmd) = &w.cmd {
+            let cmd = logged(cfg, proj_dir, &w.name, cmd);
+            send_to_target_sh(tmux, &format!("{session}:{}", w.name), &dir, env, &cmd)?;
+        }
+    }
+    Ok(())
+}
+
+/// `cmd` with its output also written to a log file: `<log_dir>/<window>.log`
+/// when a log dir is configured, `logs/app.log` in the app's folder otherwise.
+fn logged(cfg: &ResolvedConfig, proj_dir: &Path, window: &str, cmd: &str) -> String {
+    let quote = |s: String| shlex::try_quote(&s).map_or(s.clone(), |q| q.into_owned());
+    // A subshell so `a; b` is logged whole and a `cd` in it doesn't move tee
+    match &cfg.log_dir {
+        Some(dir) => {
+            let dir = proj_dir.join(dir);
+            let log = quote(dir.join(format!("{window}.log")).display().to_string());
+            let dir = quote(dir.display().to_string());
+            format!("mkdir -p {dir} && ( {cmd} ) 2>&1 | tee {log}")
+        }
+        None => format!("mkdir -p logs && ( {cmd} ) 2>&1 | tee logs/app.log"),
     }
 }
 
-fn seed_docker(tmux: &Tmux, proj_dir: &Path, panes: &[String]) -> Result<()> {
+fn no_dev_cmd() -> String {
+    format!(
+        "echo 'No dev command detected'; {}",
+        env::var("SHELL").unwrap_or_else(|_| "sh".to_string())
+    )
+}
+
+fn seed_docker(
+    tmux: &Tmux,
+    proj_dir: &Path,
+    env: &BTreeMap<String, String>,
+    panes: &[String],
+) -> Result<()> {
     // panes[0] => compose/watch
     if let Some(compose) = compose_file(proj_dir) {
         let path = compose.display().to_string();
         let cmd =
             format!("docker compose -f {path} up -d && watch -n 1 'docker compose -f {path} ps'");
-        send_to_target_sh(tmux, &panes[0], proj_dir, &cmd)?;
+        send_to_target_sh(tmux, &panes[0], proj_dir, env, &cmd)?;
     } else {
         let cmd = r#"watch -n 1 "docker ps --format 'table {{.Names}}\t{{.Image}}\t{{.Status}}'""#;
-        send_to_target_sh(tmux, &panes[0], proj_dir, cmd)?;
+        send_to_target_sh(tmux, &panes[0], proj_dir, env, cmd)?;
     }
 
     // panes[1] => Postgres logs (best-effort)
     let pg_cmd = r#"docker ps --format '{{.Names}} | grep -Ei 'postgres|pg' | head -n1 | xargs -r docker logs -f || echo 'No postgres'"#;
-    send_to_target_sh(tmux, &panes[1], proj_dir, pg_cmd)?;
+    send_to_target_sh(tmux, &panes[1], proj_dir, env, pg_cmd)?;
 
     // panes[3] => Redis logs (best-effort)
     let redis_cmd = r#"docker ps --format '{{.Names}}' | grep -Ei '^redis' | head -n1 | xargs -r docker logs -f || echo 'No redis'"#;
-    send_to_target_sh(tmux, &panes[2], proj_dir, redis_cmd)?;
+    send_to_target_sh(tmux, &panes[2], proj_dir, env, redis_cmd)?;
 
     // panes[4] => spare/interactive
-    send_to_target_sh(tmux, &panes[3], proj_dir, "exec $SHELL")?;
+    send_to_target_sh(tmux, &panes[3], proj_dir, env, "exec $SHELL")?;
     Ok(())
 }
@@ -0,0 +1,231 @@
+use crate::cli::Shell;
+use crate::environment::is_var_name;
+use anyhow::Result;
+use std::path::Path;
+
+/// Per-shell current project, set by the `shell-init` wrapper.
+pub const PROJECT_VAR: &str = "PROJCTL_PROJECT";
+/// Project this shell used before `PROJECT_VAR`, for `projctl use -`.
+pub const PREVIOUS_VAR: &str = "PROJCTL_PREVIOUS";
+/// This shell's `projctl push` stack, one name per line, next pop last.
+pub const STACK_VAR: &str = "PROJCTL_STACK";
+
+/// Wrapper function printed by `projctl shell-init`. `use`, `cd`, `push` and
+/// `pop` (also after global options like `--profile`) are routed through
+/// `projctl <cmd> --emit <shell>`, whose stdout is evaluated so the switch
+/// (and the `cd`) happens in the calling shell; everything else goes
+/// straight to the binary. Completion for `projctl cd` is included, and
+/// with `auto_switch` a hook that follows the shell into project folders.
+pub fn init_script(shell: Shell, auto_switch: bool) -> String {
+    let hook = match (auto_switch, shell) {
+        (false, _) => "",
+        (true, Shell::Bash) => BASH_HOOK,
+        (true, Shell::Zsh) => ZSH_HOOK,
+        (true, Shell::Fish) => FISH_HOOK,
+    };
+    let script = match shell {
+        Shell::Bash | Shell::Zsh => {
+            let name = shell_name(shell);
+            let completion = if let Shell::Bash = shell {
+                BASH_COMPLETION
+            } else {
+                ZSH_COMPLETION
+            };
+            format!(
+                r#"# projctl shell integration ({name})
+# Add to your rc file: eval "$(projctl shell-init {name})"
+projctl() {{
+    local -a __projctl_opts=()
+    # Global options may come before the subcommand
+    while [ $# -gt 0 ]; do
+        case "$1" in
+            --editor|--git-ui|--config|-p|--project|--profile)
+                [ $# -ge 2 ] || break
+                __projctl_opts+=("$1" "$2")
+                shift 2
+                ;;
+            -p?*|--*=*)
+                __projctl_opts+=("$1")
+                shift
+                ;;
+            *)
+                break
+                ;;
+        esac
+    done
+    case "$1" in
+        use|cd|push|pop)
+            local __projctl_cmd="$1" __projctl_out __projctl_arg
+            shift
+            # Help goes to the terminal, not through eval
+            for __projctl_arg in "$@"; do
+                case "$__projctl_arg" in
+                    -h|--help|-V|--version)
+                        command projctl "${{__projctl_opts[@]}}" "$__projctl_cmd" "$@"
+                        return
+                        ;;
+                esac
+            done
+            __projctl_out="$(command projctl "${{__projctl_opts[@]}}" "$__projctl_cmd" --emit {name} "$@")" || return $?
+            eval "$__projctl_out"
+            ;;
+        *)
+            command projctl "${{__projctl_opts[@]}}" "$@"
+            ;;
+    esac
+}}
+{completion}"#
+            )
+        }
+        Shell::Fish => r#"# projctl shell integration (fish)
+# Add to config.fish: projctl shell-init fish | source
+function projctl
+    set -l __projctl_opts
+    # Global options may come before the subcommand
+    while set -q argv[1]
+        switch $argv[1]
+            case --editor --git-ui --config -p --project --profile
+                set -q argv[2]; or break
+                set -a __projctl_opts $argv[1..2]
+                set -e argv[1..2]
+            case '-p?*' '--*=*'
+                set -a __projctl_opts $argv[1]
+                set -e argv[1]
+            case '*'
+                break
+        end
+    end
+    # Help goes to the terminal, not through `source`
+    set -l __projctl_help (string match -r -- '^(-h|--help|-V|--version)$' $argv)
+    if set -q argv[1]; and contains -- "$argv[1]" use cd push pop; and not set -q __projctl_help[1]
+        set -l __projctl_out (command projctl $__projctl_opts $argv[1] --emit fish $argv[2..-1]); or return $status
+        printf '%s\n' $__projctl_out | source
+    else
+        command projctl $__projctl_opts $argv
+    end
+end
+
+complete -c projctl -n '__fish_seen_subcommand_from cd; and test (count (commandline -opc)) -eq 2' -f -a '(command projctl complete projects 2>/dev/null)'
+complete -c projctl -n '__fish_seen_subcommand_from cd; and test (count (commandline -opc)) -eq 3' -f -a '(command projctl complete subdirs (commandline -opc)[3] (commandline -ct) 2>/dev/null)'
+"#
+        .to_string(),
+    };
+    script + hook
+}
+
+const BASH_HOOK: &str = r#"
+__projctl_chpwd() {
+    [ "$PWD" = "${__projctl_pwd-}" ] && return
+    __projctl_pwd="$PWD"
+    eval "$(command projctl chpwd bash)"
+}
+case ";${PROMPT_COMMAND-};" in
+    *";__projctl_chpwd;"*) ;;
+    *) PROMPT_COMMAND="__projctl_chpwd${PROMPT_COMMAND:+;$PROMPT_COMMAND}" ;;
+esac
+"#;
+
+const ZSH_HOOK: &str = r#"
+__projctl_chpwd() {
+    eval "$(command projctl chpwd zsh)"
+}
+autoload -Uz add-zsh-hook
+add-zsh-hook chpwd __projctl_chpwd
+__projctl_chpwd
+"#;
+
+const FISH_HOOK: &str = r#"
+function __projctl_chpwd --on-variable PWD
+    command projctl chpwd fish | source
+end
+__projctl_chpwd
+"#;
+
+const BASH_COMPLETION: &str = r#"
+_projctl_complete() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    [ "${COMP_WORDS[1]}" = cd ] || return 0
+    case "$COMP_CWORD" in
+        2)
+            COMPREPLY=($(compgen -W "$(command projctl complete projects 2>/dev/null)" -- "$cur"))
+            ;;
+        3)
+            COMPREPLY=($(command projctl complete subdirs "${COMP_WORDS[2]}" "$cur" 2>/dev/null))
+            compopt -o nospace 2>/dev/null
+            ;;
+    esac
+}
+complete -o default -F _projctl_complete projctl
+"#;
+
+const ZSH_COMPLETION: &str = r#"
+_projctl_complete() {
+    if [[ ${words[2]} == cd ]] && (( CURRENT == 3 )); then
+        compadd -- ${(f)"$(command projctl complete projects 2>/dev/null)"}
+    elif [[ ${words[2]} == cd ]] && (( CURRENT == 4 )); then
+        compadd -S '' -- ${(f)"$(command projctl complete subdirs ${words[3]} ${words[4]} 2>/dev/null)"}
+    else
+        _files
+    fi
+}
+(( $+functions[compdef] )) && compdef _projctl_complete projctl
+"#;
+
+/// A line that exports `key=value` in `shell`.
+pub fn export_var(shell: Shell, key: &str, value: &str) -> Result<String> {
+    // The key can't be quoted, so it must be a plain name
+    if !is_var_name(key) {
+        anyhow::bail!("Invalid variable name '{}'", key);
+    }
+    Ok(match shell {
+        Shell::Bash | Shell::Zsh => format!("export {key}={}", quote(shell, value)),
+        Shell::Fish => format!("set -gx {key} {}", quote(shell, value)),
+    })
+}
+
+/// A line that changes the shell's directory to `dir`.
+pub fn cd_line(shell: Shell, dir: &Path) -> String {
+    let dir = quote(shell, &dir.display().to_string());
+    match shell {
+        Shell::Bash | Shell::Zsh => format!("cd -- {dir}"),
+        Shell::Fish => format!("cd {dir}"),
+    }
+}
+
+pub fn quote(shell: Shell, s: &str) -> String {
+    match shell {
+        Shell::Bash | Shell::Zsh => format!("'{}'", s.replace('\'', r"'\''")),
+        Shell::Fish => format!("'{}'", s.replace('\\', r"\\").replace('\'', r"\'")),
+    }
+}
+
+fn shell_name(shell: Shell) -> &'static str {
+    match shell {
+        Shell::Bash => "bash",
+        Shell::Zsh => "zsh",
+        Shell::Fish => "fish",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_for_each_shell() {
+        assert_eq!(quote(Shell::Bash, "it's"), r"'it'\''s'");
+        assert_eq!(quote(Shell::Zsh, "$(x)"), "'$(x)'");
+        assert_eq!(quote(Shell::Fish, r"it's \"), r"'it\'s \\'");
+    }
+
+    #[test]
+    fn exports_only_plain_names() {
+        assert_eq!(
+            export_var(Shell::Bash, "A_1", "x y").unwrap(),
+            "export A_1='x y'"
+        );
+        assert_eq!(export_var(Shell::Fish, "A", "x").unwrap(), "set -gx A 'x'");
+        assert!(export_var(Shell::Bash, "A=1;rm", "x").is_err());
+        assert!(export_var(Shell::Zsh, "", "x").is_err());
+    }
+}
@@ -0,0 +1,381 @@
+use crate::models::{ProjectEntry, Projects, REGISTRY_VERSION};
+use anyhow::{Context, Result};
+use dialoguer::Confirm;
+use serde_json::Value;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// Number of rotating `projects.json.bak.N` copies kept next to the registry.
+const BACKUP_COUNT: usize = 3;
+
+/// Where the project registry lives. Commands only talk to the registry
+/// through this trait, so the library can be embedded or pointed at a
+/// throwaway store.
+pub trait ProjectStore {
+    /// Create the backing storage if it doesn't exist yet.
+    fn ensure(&self) -> Result<()>;
+
+    fn load(&self) -> Result<Projects>;
+
+    /// [`load`](ProjectStore::load) for prompts and shell hooks: never
+    /// blocks on the lock, writes (a v1 registry is migrated in memory only)
+    /// or asks anything (a corrupt registry is just an error).
+    fn load_readonly(&self) -> Result<Projects> {
+        self.load()
+    }
+
+    /// Run one load-modify-save cycle atomically with respect to other users
+    /// of the same store. Nothing is saved if `f` fails. Callers normally go
+    /// through the typed `update` wrapper on `dyn ProjectStore`.
+    fn transact(&self, f: &mut dyn FnMut(&mut Projects) -> Result<()>) -> Result<()>;
+}
+
+impl dyn ProjectStore + '_ {
+    /// [`ProjectStore::transact`] that can return a value from the closure.
+    pub fn update<T>(&self, f: impl FnOnce(&mut Projects) -> Result<T>) -> Result<T> {
+        let mut f = Some(f);
+        let mut out = None;
+        self.transact(&mut |projects| {
+            let f = f.take().expect("transact runs the closure once");
+            out = Some(f(projects)?);
+            Ok(())
+        })?;
+        Ok(out.expect("transact succeeded without running the closure"))
+    }
+}
+
+/// The registry as a JSON file (normally `$XDG_CONFIG_HOME/projctl/projects.json`),
+/// guarded by an advisory lock, written atomically and backed up on every
+/// save.
+pub struct JsonFileStore {
+    path: PathBuf,
+}
+
+impl JsonFileStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl ProjectStore for JsonFileStore {
+    fn ensure(&self) -> Result<()> {
+        let _lock = RegistryLock::acquire(&self.path)?;
+        if !self.path.exists() {
+            write_projects(&self.path, &Projects::default())?;
+        }
+        Ok(())
+    }
+
+    fn load(&self) -> Result<Projects> {
+        let _lock = RegistryLock::acquire(&self.path)?;
+        read_projects(&self.path)
+    }
+
+    fn load_readonly(&self) -> Result<Projects> {
+        // Saves replace the file by rename, so an unlocked read sees a
+        // whole registry
+        let contents = fs::read_to_string(&self.path)
+            .with_context(|| format!("reading {}", self.path.display()))?;
+        let raw: Value = serde_json::from_str(&contents)
+            .with_context(|| format!("parsing {}", self.path.display()))?;
+        match is_v2(&raw) {
+            true => parse_v2(&self.path, raw),
+            false => migrate_v1(raw),
+        }
+    }
+
+    fn transact(&self, f: &mut dyn FnMut(&mut Projects) -> Result<()>) -> Result<()> {
+        let _lock = RegistryLock::acquire(&self.path)?;
+        let mut projects = read_projects(&self.path)?;
+        f(&mut projects)?;
+        write_projects(&self.path, &projects)
+    }
+}
+
+/// A registry that lives only in memory, for embedding and tests.
+#[derive(Default)]
+pub struct MemoryStore {
+    projects: Mutex<Projects>,
+}
+
+impl MemoryStore {
+    pub fn new(projects: Projects) -> Self {
+        Self {
+            projects: Mutex::new(projects),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Projects> {
+        self.projects.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl ProjectStore for MemoryStore {
+    fn ensure(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn load(&self) -> Result<Projects> {
+        Ok(self.lock().clone())
+    }
+
+    fn transact(&self, f: &mut dyn FnMut(&mut Projects) -> Result<()>) -> Result<()> {
+        let mut guard = self.lock();
+        let mut projects = guard.clone();
+        f(&mut projects)?;
+        *guard = projects;
+        Ok(())
+    }
+}
+
+/// Advisory lock on a sidecar file next to the registry. The registry itself
+/// is replaced by rename on every save, so it can't carry the lock. Released
+/// when dropped.
+struct RegistryLock {
+    _file: File,
+}
+
+impl RegistryLock {
+    fn acquire(db_path: &Path) -> Result<Self> {
+        if let Some(parent) = db_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let lock_path = sibling(db_path, ".lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("opening {}", lock_path.display()))?;
+        file.lock()
+            .with_context(|| format!("locking {}", lock_path.display()))?;
+        Ok(Self { _file: file })
+    }
+}
+
+fn read_projects(db_path: &Path) -> Result<Projects> {
+    let contents =
+        fs::read_to_string(db_path).with_context(|| format!("reading {}", db_path.display()))?;
+    let raw: Value = match serde_json::from_str(&contents) {
+        Ok(raw) => raw,
+        Err(e) => return recover_from_backup(db_path, e),
+    };
+
+    if is_v2(&raw) {
+        return parse_v2(db_path, raw);
+    }
+
+    // v1: `current` next to a flat map of name -> path
+    let projects = migrate_v1(raw)?;
+    backup_v1(db_path)?;
+    write_projects(db_path, &projects)?;
+    eprintln!(
+        "Migrated {} to registry version {}",
+        db_path.display(),
+        REGISTRY_VERSION
+    );
+    Ok(projects)
+}
+
+/// v1 keys are project names, so a project may be called `version`; only a
+/// numeric version next to a `projects` object means v2.
+fn is_v2(raw: &Value) -> bool {
+    raw.get("version").is_some_and(Value::is_u64)
+        && raw.get("projects").is_some_and(Value::is_object)
+}
+
+fn parse_v2(db_path: &Path, raw: Value) -> Result<Projects> {
+    let projects: Projects =
+        serde_json::from_value(raw).with_context(|| format!("parsing {}", db_path.display()))?;
+    if projects.version > REGISTRY_VERSION {
+        anyhow::bail!(
+            "{} uses registry version {}, but this projctl only understands up to {}",
+            db_path.display(),
+            projects.version,
+            REGISTRY_VERSION
+        );
+    }
+    Ok(projects)
+}
+
+/// Write to a temp file in the same directory, then rename it over the
+/// registry so readers never observe a half-written file.
+fn write_projects(db_path: &Path, projects: &Projects) -> Result<()> {
+    let json = serde_json::to_string_pretty(projects)?;
+    rotate_backups(db_path)?;
+
+    let tmp_path = sibling(db_path, ".tmp");
+    {
+        let mut tmp =
+            File::create(&tmp_path).with_context(|| format!("creating {}", tmp_path.display()))?;
+        tmp.write_all(json.as_bytes())?;
+        tmp.sync_all()?;
+    }
+    fs::rename(&tmp_path, db_path).with_context(|| format!("replacing {}", db_path.display()))?;
+    Ok(())
+}
+
+/// Shift `projects.json.bak.N` up by one and copy the current registry into
+/// `.bak.1`. A registry that doesn't parse is never rotated in, so the
+/// backups only ever hold known-good copies.
+fn rotate_backups(db_path: &Path) -> Result<()> {
+    let Ok(current) = fs::read_to_string(db_path) else {
+        return Ok(());
+    };
+    if serde_json::from_str::<Value>(&current).is_err() {
+        return Ok(());
+    }
+    for n in (1..BACKUP_COUNT).rev() {
+        let from = backup_path(db_path, n);
+        if from.exists() {
+            fs::rename(&from, backup_path(db_path, n + 1))?;
+        }
+    }
+    fs::write(backup_path(db_path, 1), current)?;
+    Ok(())
+}
+
+fn recover_from_backup(db_path: &Path, err: serde_json::Error) -> Result<Projects> {
+    let backup = (1..=BACKUP_COUNT)
+        .map(|n| backup_path(db_path, n))
+        .find(|p| {
+            fs::read_to_string(p)
+                .ok()
+                .is_some_and(|s| serde_json::from_str::<Value>(&s).is_ok())
+        });
+    let Some(backup) = backup else {
+        anyhow::bail!(
+            "{} is corrupt ({err}) and no usable backup was found",
+            db_path.display()
+        );
+    };
+
+    eprintln!("{} could not be parsed: {err}", db_path.display());
+    let restore = Confirm::new()
+        .with_prompt(format!("Restore from backup {}?", backup.display()))
+        .default(true)
+        .interact()
+        .unwrap_or(false);
+    if !restore {
+        anyhow::bail!(
+            "{} is corrupt; fix it by hand or restore {}",
+            db_path.display(),
+            backup.display()
+        );
+    }
+
+    let corrupt = sibling(db_path, ".corrupt");
+    fs::copy(db_path, &corrupt)?;
+    let tmp_path = sibling(db_path, ".tmp");
+    fs::copy(&backup, &tmp_path)?;
+    fs::rename(&tmp_path, db_path)?;
+    eprintln!(
+        "Restored {} (corrupt copy kept at {})",
+        db_path.display(),
+        corrupt.display()
+    );
+    read_projects(db_path)
+}
+
+fn sibling(db_path: &Path, suffix: &str) -> PathBuf {
+    let mut name = db_path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn backup_path(db_path: &Path, n: usize) -> PathBuf {
+    sibling(db_path, &format!(".bak.{n}"))
+}
+
+fn migrate_v1(raw: Value) -> Result<Projects> {
+    let Value::Object(map) = raw else {
+        anyhow::bail!("unrecognized registry format: expected a JSON object");
+    };
+    let mut projects = Projects::default();
+    for (key, value) in map {
+        match (key.as_str(), value) {
+            ("current", Value::String(name)) => projects.current = Some(name),
+            ("current", _) => {}
+            (_, Value::String(path)) => {
+                projects.projects.insert(
+                    key,
+                    ProjectEntry {
+                        path,
+                        ..Default::default()
+                    },
+                );
+            }
+            (_, other) => anyhow::bail!("unexpected v1 registry value for '{key}': {other}"),
+        }
+    }
+    Ok(projects)
+}
+
+fn backup_v1(db_path: &Path) -> Result<()> {
+    let backup = sibling(db_path, ".v1.bak");
+    fs::copy(db_path, &backup)
+        .with_context(|| format!("backing up {} before migration", db_path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn detects_v2_only_by_shape() {
+        assert!(is_v2(&json!({"version": 2, "projects": {}})));
+        // v1 projects named `version` and `projects`
+        assert!(!is_v2(
+            &json!({"version": "/src/version", "api": "/src/api"})
+        ));
+        assert!(!is_v2(&json!({"version": "/v", "projects": "/p"})));
+    }
+
+    #[test]
+    fn migrates_v1_entries_and_current() {
+        let projects = migrate_v1(json!({
+            "current": "api",
+            "api": "/src/api",
+            "version": "/src/version",
+        }))
+        .unwrap();
+        assert_eq!(projects.version, REGISTRY_VERSION);
+        assert_eq!(projects.current.as_deref(), Some("api"));
+        assert_eq!(projects.projects["api"].path, "/src/api");
+        assert_eq!(projects.projects["version"].path, "/src/version");
+        assert_eq!(projects.projects.len(), 2);
+    }
+
+    #[test]
+    fn rejects_unexpected_v1_values() {
+        assert!(migrate_v1(json!({"api": 1})).is_err());
+        assert!(migrate_v1(json!(["api"])).is_err());
+    }
+
+    #[test]
+    fn memory_store_discards_failed_updates() {
+        let store = MemoryStore::default();
+        let store: &dyn ProjectStore = &store;
+        store
+            .update(|p| {
+                p.projects
+                    .insert("api".into(), ProjectEntry::new("/src/api"));
+                Ok(())
+            })
+            .unwrap();
+        let failed = store.update::<()>(|p| {
+            p.projects.clear();
+            anyhow::bail!("nope")
+        });
+        assert!(failed.is_err());
+        assert!(store.load().unwrap().projects.contains_key("api"));
+    }
+}
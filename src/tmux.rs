@@ -1,4 +1,6 @@
+use crate::environment::is_var_name;
 use anyhow::{Context, Result};
+use std::collections::BTreeMap;
 use std::env;
 use std::path::Path;
 use std::process::{Command, Stdio};
@@ -103,10 +105,28 @@ pub fn setup_docker_layout(tmux: &Tmux, session: &str) -> Result<Vec<String>> {
     Ok(panes)
 }
 
-pub fn send_to_target_sh(tmux: &Tmux, target: &str, cwd: &Path, cmd: &str) -> Result<()> {
-    let cd = shell_escape(cwd);
-    let line = format!("cd {cd} && clear && {cmd}");
-    tmux.run(["send-keys", "-t", target, &line, "C-m"])
+/// Restart the pane at `target` as a fresh shell in `cwd` with `env`, then
+/// type `cmd` into it. The environment goes through `respawn-pane -e` rather
+/// than typed `export`s, so values never show up in the pane's scrollback or
+/// shell history, and any value (newlines included) survives as is.
+pub fn send_to_target_sh(
+    tmux: &Tmux,
+    target: &str,
+    cwd: &Path,
+    env: &BTreeMap<String, String>,
+    cmd: &str,
+) -> Result<()> {
+    if let Some(k) = env.keys().find(|k| !is_var_name(k)) {
+        anyhow::bail!("Invalid variable name '{}'", k);
+    }
+    let cwd = cwd.to_string_lossy();
+    let vars: Vec<String> = env.iter().map(|(k, v)| format!("{k}={v}")).collect();
+    let mut args = vec!["respawn-pane", "-k", "-t", target, "-c", &cwd];
+    for var in &vars {
+        args.extend(["-e", var.as_str()]);
+    }
+    tmux.run(args)?;
+    tmux.run(["send-keys", "-t", target, &format!("clear && {cmd}"), "C-m"])
 }
 
 pub fn attach_or_switch(tmux: &Tmux, session: &str) -> Result<()> {
@@ -116,8 +136,3 @@ pub fn attach_or_switch(tmux: &Tmux, session: &str) -> Result<()> {
         tmux.run(["attach-session", "-t", session])
     }
 }
-
-fn shell_escape(p: &Path) -> String {
-    let s = p.to_string_lossy();
-    format!("'{}'", s.replace('\'', r"'\''"))
-}
@@ -1,32 +1,191 @@
+use crate::config::{PROJECT_FILE, ResolvedConfig, ScanRoot};
+use crate::models::Projects;
+use crate::resolve::{resolve_exact, resolve_project};
+use crate::shell::PROJECT_VAR;
+use crate::store::ProjectStore;
 use anyhow::{Context, Result};
 use shellexpand::tilde;
 use std::env;
 use std::fs;
 use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-pub const PROJECTS_DB: &str = "~/.config/projctl/projects.json";
-pub const STATE: &str = "~/.cache/current_project";
+pub const PROJECTS_DB: &str = "projects.json";
+pub const STATE: &str = "current_project";
 pub const PROJECTS_DIR: &str = "~/projects";
 
+/// Where the current project was kept before projctl followed XDG.
+const LEGACY_STATE: &str = "~/.cache/current_project";
+
 pub fn expand_tilde(path: &str) -> PathBuf {
     PathBuf::from(tilde(path).into_owned())
 }
 
+/// `PROJCTL_HOME`, if set, relocates config, data, state and cache at once
+/// (to `config/`, `data/`, `state/` and `cache/` under it).
+fn projctl_home() -> Option<PathBuf> {
+    env::var_os("PROJCTL_HOME")
+        .filter(|v| !v.is_empty())
+        .map(|v| expand_tilde(&v.to_string_lossy()))
+}
+
+/// `$var` if it's set to an absolute path (relative values are invalid per
+/// the XDG spec), otherwise `fallback`.
+fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .unwrap_or_else(|| expand_tilde(fallback))
+}
+
+pub fn config_dir() -> PathBuf {
+    match projctl_home() {
+        Some(home) => home.join("config"),
+        None => xdg_dir("XDG_CONFIG_HOME", "~/.config").join("projctl"),
+    }
+}
+
+pub fn state_dir() -> PathBuf {
+    match projctl_home() {
+        Some(home) => home.join("state"),
+        None => xdg_dir("XDG_STATE_HOME", "~/.local/state").join("projctl"),
+    }
+}
+
+pub fn cache_dir() -> PathBuf {
+    match projctl_home() {
+        Some(home) => home.join("cache"),
+        None => xdg_dir("XDG_CACHE_HOME", "~/.cache").join("projctl"),
+    }
+}
+
+pub fn data_dir() -> PathBuf {
+    match projctl_home() {
+        Some(home) => home.join("data"),
+        None => xdg_dir("XDG_DATA_HOME", "~/.local/share").join("projctl"),
+    }
+}
+
+/// Markdown notes file for a project (it may not exist yet). `%`, path
+/// separators and a leading dot are percent-encoded, so every name gets its
+/// own file inside `notes/`.
+pub fn note_path(name: &str) -> PathBuf {
+    let mut file = String::new();
+    for (i, c) in name.chars().enumerate() {
+        match c {
+            '%' | '/' | '\\' => file.push_str(&format!("%{:02X}", c as u32)),
+            '.' if i == 0 => file.push_str("%2E"),
+            c => file.push(c),
+        }
+    }
+    profile_dir(data_dir(), profile())
+        .join("notes")
+        .join(format!("{file}.md"))
+}
+
+pub fn has_note(name: &str) -> bool {
+    fs::metadata(note_path(name)).is_ok_and(|m| m.len() > 0)
+}
+
+/// Selects the profile when `--profile` isn't given.
+pub const PROFILE_VAR: &str = "PROJCTL_PROFILE";
+
+static PROFILE: OnceLock<Option<String>> = OnceLock::new();
+
+/// Select the profile for this run (once, before anything touches the
+/// registry). `None` is the default profile.
+pub fn set_profile(name: Option<String>) {
+    let _ = PROFILE.set(name);
+}
+
+pub fn profile() -> Option<&'static str> {
+    PROFILE.get().and_then(|p| p.as_deref())
+}
+
+/// Where a profile keeps its files under `dir`; the default profile uses
+/// `dir` itself.
+pub fn profile_dir(dir: PathBuf, profile: Option<&str>) -> PathBuf {
+    match profile {
+        Some(name) => dir.join("profiles").join(name),
+        None => dir,
+    }
+}
+
 pub fn get_projects_db() -> PathBuf {
-    expand_tilde(PROJECTS_DB)
+    profile_dir(config_dir(), profile()).join(PROJECTS_DB)
 }
 
 pub fn get_state() -> PathBuf {
-    expand_tilde(STATE)
+    profile_dir(state_dir(), profile()).join(STATE)
 }
 
-pub fn get_projects_dir() -> PathBuf {
-    expand_tilde(PROJECTS_DIR)
+/// Move a state file written by an older projctl to `state_path`. Done once,
+/// so clearing the current project later doesn't bring the old one back.
+fn migrate_legacy_state(state_path: &Path) {
+    let legacy = expand_tilde(LEGACY_STATE);
+    if !legacy.exists() {
+        return;
+    }
+    let moved = state_path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| {
+            fs::rename(&legacy, state_path).or_else(|_| {
+                // Across filesystems
+                fs::copy(&legacy, state_path)?;
+                fs::remove_file(&legacy)
+            })
+        });
+    if let Err(e) = moved {
+        eprintln!(
+            "warning: could not move {} to {}: {}",
+            legacy.display(),
+            state_path.display(),
+            e
+        );
+    }
 }
 
-pub fn get_current_projdir() -> Result<PathBuf> {
+/// The current project's folder, from (in order) an explicit `--project`,
+/// the innermost registered project containing the cwd, this shell's
+/// `PROJCTL_PROJECT` (set by the `shell-init` wrapper), and the global state
+/// file.
+pub fn get_current_projdir(
+    store: &dyn ProjectStore,
+    cfg: &ResolvedConfig,
+    project: Option<&str>,
+) -> Result<PathBuf> {
+    if let Some(name) = project {
+        let (_, path) = find_project(store, cfg, name)?;
+        if !path.exists() {
+            anyhow::bail!("Project path {} does not exist", path.display());
+        }
+        return Ok(path);
+    }
+
+    if let Ok(cwd) = env::current_dir()
+        && let Some((_, path)) = project_containing(&store.load()?, &cwd)
+    {
+        return Ok(path);
+    }
+
+    // The shell may have switched under another profile; an unknown name
+    // falls through to the state file
+    if let Some(name) = env::var(PROJECT_VAR).ok().filter(|n| !n.is_empty())
+        && let Some(path) =
+            get_projdir(store, &name)?.or_else(|| get_autodetected_projdir(cfg, &name))
+    {
+        if !path.exists() {
+            anyhow::bail!("Current project path does not exist");
+        }
+        return Ok(path);
+    }
+
     let state_path = get_state();
+    if !state_path.exists() && projctl_home().is_none() && profile().is_none() {
+        migrate_legacy_state(&state_path);
+    }
     let mut file = std::fs::File::open(&state_path).context("No current project set")?;
     let mut path_str = String::new();
     file.read_to_string(&mut path_str)?;
@@ -37,40 +196,81 @@ pub fn get_current_projdir() -> Result<PathBuf> {
     Ok(path)
 }
 
-pub fn get_projdir(name: &str) -> Result<Option<PathBuf>> {
-    let projects = super::models::load_projects()?;
-    Ok(projects.projects.get(name).map(PathBuf::from))
+/// Look up a project given by name on the command line: an added project or
+/// alias, an auto-detected folder, then prefix/fuzzy matches. Returns the
+/// exact name, so a second lookup won't prompt again.
+pub fn find_project(
+    store: &dyn ProjectStore,
+    cfg: &ResolvedConfig,
+    name: &str,
+) -> Result<(String, PathBuf)> {
+    if let Some(found) = find_project_exact(store, cfg, name)? {
+        return Ok(found);
+    }
+    let projects = store.load()?;
+    let r = resolve_project(&projects, name)?;
+    let path = PathBuf::from(&projects.projects[&r.name].path);
+    Ok((r.name, path))
+}
+
+/// [`find_project`] without the prefix/fuzzy steps, so it never prompts:
+/// an added project or alias, then an auto-detected folder.
+pub fn find_project_exact(
+    store: &dyn ProjectStore,
+    cfg: &ResolvedConfig,
+    name: &str,
+) -> Result<Option<(String, PathBuf)>> {
+    let projects = store.load()?;
+    if let Some(r) = resolve_exact(&projects, name) {
+        let path = PathBuf::from(&projects.projects[&r.name].path);
+        return Ok(Some((r.name, path)));
+    }
+    Ok(get_autodetected_projdir(cfg, name).map(|p| (name.to_string(), p)))
+}
+
+/// The innermost registered project whose folder contains `path`.
+pub fn project_containing(projects: &Projects, path: &Path) -> Option<(String, PathBuf)> {
+    let path = canon(path);
+    projects
+        .projects
+        .iter()
+        .map(|(name, e)| (name, canon(Path::new(&e.path))))
+        .filter(|(_, dir)| path.starts_with(dir))
+        .max_by_key(|(_, dir)| dir.components().count())
+        .map(|(name, dir)| (name.clone(), dir))
+}
+
+pub fn get_projdir(store: &dyn ProjectStore, name: &str) -> Result<Option<PathBuf>> {
+    let projects = store.load()?;
+    Ok(projects.projects.get(name).map(|e| PathBuf::from(&e.path)))
 }
 
 /// Detects a dev command for a given directory (JS, Rust, Go, Python).
-pub fn detect_dev_cmd(dir: &Path) -> String {
-    if dir.join("package.json").exists() {
-        "mkdir -p logs && (pnpm run dev || npm run dev || yarn dev) 2>&1 | tee logs/app.log"
-            .to_string()
+pub fn detect_dev_cmd(dir: &Path) -> Option<String> {
+    let cmd = if dir.join("package.json").exists() {
+        "(pnpm run dev || npm run dev || yarn dev)"
     } else if dir.join("Cargo.toml").exists() {
         if which::which("cargo-watch").is_ok() {
-            "mkdir -p logs && cargo watch -x run 2>&1 | tee logs/app.log".to_string()
+            "cargo watch -x run"
         } else {
-            "mkdir -p logs && cargo run 2>&1 | tee logs/app.log".to_string()
+            "cargo run"
         }
     } else if dir.join("go.mod").exists() {
         if which::which("air").is_ok() {
-            "mkdir -p logs && air 2>&1 | tee logs/app.log".to_string()
+            "air"
         } else {
-            "mkdir -p logs && go run ./... 2>&1 | tee logs/app.log".to_string()
+            "go run ./..."
         }
     } else if dir.join("pyproject.toml").exists() || dir.join("requirements.txt").exists() {
         if which::which("uv").is_ok() {
-            "mkdir -p logs && uv run python -m app 2>&1 | tee logs/app.log".to_string()
+            "uv run python -m app"
         } else {
-            "mkdir -p logs && python -m app 2>&1 | tee logs/app.log".to_string()
+            "python -m app"
         }
     } else {
-        format!(
-            "echo 'No dev command detected'; {}",
-            env::var("SHELL").unwrap_or_else(|_| "sh".to_string())
-        )
-    }
+        return None;
+    };
+    Some(cmd.to_string())
 }
 
 pub fn guess_frontend_dir(proj_dir: &Path) -> Option<PathBuf> {
@@ -110,9 +310,22 @@ pub fn compose_file(proj_dir: &Path) -> Option<PathBuf> {
     None
 }
 
-pub fn get_autodetected_projdir(name: &str) -> Option<PathBuf> {
-    let cand = get_projects_dir().join(name);
-    if cand.is_dir() { Some(cand) } else { None }
+/// Folder of the auto-detected project `name`: `name` as a path below one of
+/// the scan roots, or else a folder by that name found deeper by the scan.
+/// The direct check comes first since the scan walks every root.
+pub fn get_autodetected_projdir(cfg: &ResolvedConfig, name: &str) -> Option<PathBuf> {
+    if let Some(p) = cfg
+        .scan_roots
+        .iter()
+        .map(|r| expand_tilde(&r.path).join(name))
+        .find(|p| p.is_dir())
+    {
+        return Some(p);
+    }
+    autodetected_projects(cfg)
+        .into_iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, p)| p)
 }
 
 pub fn canon(path: &Path) -> PathBuf {
@@ -129,25 +342,132 @@ pub fn same_path(a: &Path, b: &Path) -> bool {
     canon(a) == canon(b)
 }
 
-/// Return all auto-detected projects in `~/projets`, sorted by name.
-pub fn autodetected_projects() -> Vec<(String, PathBuf)> {
-    let dir = get_projects_dir();
-    if !dir.exists() {
-        return vec![];
-    }
-    let mut v = vec![];
-    if let Ok(rd) = fs::read_dir(&dir) {
-        for ent in rd.flatten() {
-            let p = ent.path();
-            if p.is_dir() {
-                let name = ent.file_name().to_string_lossy().to_string();
-                v.push((name, p));
+/// Return all auto-detected projects under the scan roots (`projects_dir`
+/// first), sorted by name within each root. The first folder found wins when
+/// names collide.
+pub fn autodetected_projects(cfg: &ResolvedConfig) -> Vec<(String, PathBuf)> {
+    let mut v: Vec<(String, PathBuf)> = vec![];
+    for root in &cfg.scan_roots {
+        let mut found = vec![];
+        scan_dir(&expand_tilde(&root.path), root, 1, &mut found);
+        found.sort();
+        for (name, path) in found {
+            if !v.iter().any(|(n, _)| *n == name) {
+                v.push((name, path));
             }
         }
     }
     v
 }
 
+fn scan_dir(dir: &Path, root: &ScanRoot, level: usize, out: &mut Vec<(String, PathBuf)>) {
+    let Ok(rd) = fs::read_dir(dir) else {
+        return;
+    };
+    for ent in rd.flatten() {
+        let p = ent.path();
+        if !p.is_dir() {
+            continue;
+        }
+        let name = ent.file_name().to_string_lossy().to_string();
+        if root.ignore.iter().any(|pat| wildcard_match(pat, &name)) {
+            continue;
+        }
+        if level >= root.depth || is_project_dir(&p) {
+            out.push((name, p.clone()));
+        }
+        if level < root.depth && !is_project_dir(&p) {
+            scan_dir(&p, root, level + 1, out);
+        }
+    }
+}
+
+fn is_project_dir(dir: &Path) -> bool {
+    dir.join(".git").exists() || dir.join(PROJECT_FILE).exists()
+}
+
+/// Glob-style match of a whole name: `*` is any run of characters, `?` any
+/// single character.
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    let (mut pi, mut ni) = (0, 0);
+    // Position of the last `*` and the name index it was tried at
+    let mut star: Option<(usize, usize)> = None;
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == n[ni]) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ni));
+            pi += 1;
+        } else if let Some((sp, sn)) = star {
+            pi = sp + 1;
+            ni = sn + 1;
+            star = Some((sp, sn + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+/// `origin` remote URL of the git repo at `dir`, if any.
+pub fn git_remote_url(dir: &Path) -> Option<String> {
+    let out = std::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["config", "--get", "remote.origin.url"])
+        .output()
+        .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let url = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (!url.is_empty()).then_some(url)
+}
+
+/// Branch checked out in the git repo at `dir` (short commit id when
+/// detached), read straight from `HEAD` without spawning git.
+pub fn git_branch(dir: &Path) -> Option<String> {
+    let dot_git = dir.join(".git");
+    let git_dir = if dot_git.is_file() {
+        // Worktrees and submodules: `.git` is a `gitdir: <path>` pointer
+        let pointer = fs::read_to_string(&dot_git).ok()?;
+        let target = PathBuf::from(pointer.strip_prefix("gitdir:")?.trim());
+        if target.is_absolute() {
+            target
+        } else {
+            dir.join(target)
+        }
+    } else {
+        dot_git
+    };
+    let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref:") {
+        Some(r) => {
+            let r = r.trim();
+            Some(r.strip_prefix("refs/heads/").unwrap_or(r).to_string())
+        }
+        None => Some(head.chars().take(7).collect()),
+    }
+}
+
+/// Reduce a git remote URL to `host/owner/repo` so ssh and https forms of the
+/// same remote compare equal.
+pub fn normalize_remote(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/');
+    let url = url.strip_suffix(".git").unwrap_or(url);
+    let rest = match url.split_once("://") {
+        Some((_, rest)) => rest.to_string(),
+        // scp-like: git@host:owner/repo
+        None => url.replacen(':', "/", 1),
+    };
+    let rest = rest.rsplit_once('@').map_or(rest.as_str(), |(_, r)| r);
+    rest.to_lowercase()
+}
+
 pub fn parse_cmd(cmd: &str) -> (String, Vec<String>) {
     if let Some(mut parts) = shlex::split(cmd) {
         if parts.is_empty() {
@@ -158,3 +478,156 @@ pub fn parse_cmd(cmd: &str) -> (String, Vec<String>) {
     }
     (cmd.to_string(), vec![])
 }
+
+/// Placeholders tool commands may use.
+const PLACEHOLDERS: [&str; 5] = ["path", "file", "line", "name", "url"];
+
+/// Like `parse_cmd`, filling in `{path}`, `{file}`, `{line}`, `{name}` and
+/// `{url}` from `vars`. Each argument is expanded on its own, so values with
+/// spaces or quotes stay one argument. A placeholder whose value isn't set
+/// expands to nothing, taking a `:` or `+` right before it along, and an
+/// argument where none of the placeholders are set is dropped. Commands
+/// without placeholders get `fallback` appended.
+pub fn expand_cmd(
+    cmd: &str,
+    vars: &[(&str, Option<&str>)],
+    fallback: &[&str],
+) -> (String, Vec<String>) {
+    let (bin, args) = parse_cmd(cmd);
+    let uses = |arg: &str| {
+        PLACEHOLDERS
+            .iter()
+            .any(|p| arg.contains(&format!("{{{p}}}")))
+    };
+    if !args.iter().any(|a| uses(a)) {
+        let args = args
+            .into_iter()
+            .chain(fallback.iter().map(|a| a.to_string()))
+            .collect();
+        return (bin, args);
+    }
+    let args = args
+        .iter()
+        .filter_map(|arg| expand_arg(arg, vars))
+        .collect();
+    (bin, args)
+}
+
+/// One pass over `arg`, so expanded values are never expanded again.
+fn expand_arg(arg: &str, vars: &[(&str, Option<&str>)]) -> Option<String> {
+    let mut out = String::new();
+    let mut rest = arg;
+    let (mut set, mut unset) = (0, 0);
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let placeholder = PLACEHOLDERS
+            .iter()
+            .find(|p| rest[1..].starts_with(*p) && rest[1 + p.len()..].starts_with('}'));
+        match placeholder {
+            Some(p) => {
+                match vars.iter().find(|(k, _)| k == p).and_then(|(_, v)| *v) {
+                    Some(value) => {
+                        out.push_str(value);
+                        set += 1;
+                    }
+                    // `{file}:{line}` without a line is just the file
+                    None => {
+                        if out.ends_with([':', '+']) {
+                            out.pop();
+                        }
+                        unset += 1;
+                    }
+                }
+                rest = &rest[p.len() + 2..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    (set > 0 || unset == 0).then_some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_remotes() {
+        let want = "github.com/owner/repo";
+        for url in [
+            "git@github.com:owner/repo.git",
+            "https://github.com/owner/repo",
+            "https://user@GitHub.com/Owner/Repo.git/",
+            "ssh://git@github.com/owner/repo.git",
+        ] {
+            assert_eq!(normalize_remote(url), want, "{url}");
+        }
+    }
+
+    #[test]
+    fn note_files_stay_distinct_and_inside_notes() {
+        let file = |name: &str| {
+            let path = note_path(name);
+            assert_eq!(path.parent().unwrap().file_name().unwrap(), "notes");
+            path.file_name().unwrap().to_string_lossy().into_owned()
+        };
+        assert_eq!(file("api"), "api.md");
+        assert_eq!(file("a/b"), "a%2Fb.md");
+        assert_eq!(file("a_b"), "a_b.md");
+        assert_eq!(file("a%2Fb"), "a%252Fb.md");
+        assert_eq!(file("../x"), "%2E.%2Fx.md");
+        assert_eq!(file("v1.2"), "v1.2.md");
+    }
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(wildcard_match("*.bak", "old.bak"));
+        assert!(wildcard_match("a?c", "abc"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("a*b*c", "aXbYbZc"));
+        assert!(!wildcard_match("a?c", "ac"));
+        assert!(!wildcard_match("*.bak", "bak"));
+        assert!(!wildcard_match("node_modules", "node_modules2"));
+    }
+
+    fn expand(cmd: &str, vars: &[(&str, Option<&str>)]) -> Vec<String> {
+        let (bin, args) = expand_cmd(cmd, vars, &["fallback"]);
+        std::iter::once(bin).chain(args).collect()
+    }
+
+    #[test]
+    fn expands_placeholders_per_argument() {
+        let vars = [("file", Some("my file.rs")), ("line", Some("7"))];
+        assert_eq!(
+            expand("nvim +{line} {file}", &vars),
+            ["nvim", "+7", "my file.rs"]
+        );
+        assert_eq!(
+            expand("code -g {file}:{line}", &vars),
+            ["code", "-g", "my file.rs:7"]
+        );
+        // Values are not expanded again
+        let vars = [("file", Some("{line}")), ("line", Some("7"))];
+        assert_eq!(expand("ed {file}", &vars), ["ed", "{line}"]);
+    }
+
+    #[test]
+    fn leaves_out_unset_placeholders() {
+        let vars = [("file", Some("a.rs")), ("line", None)];
+        assert_eq!(expand("nvim +{line} {file}", &vars), ["nvim", "a.rs"]);
+        assert_eq!(
+            expand("code -g {file}:{line}", &vars),
+            ["code", "-g", "a.rs"]
+        );
+    }
+
+    #[test]
+    fn appends_fallback_without_placeholders() {
+        assert_eq!(expand("code -g", &[]), ["code", "-g", "fallback"]);
+        assert_eq!(expand("echo {other}", &[]), ["echo", "{other}", "fallback"]);
+    }
+}